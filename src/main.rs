@@ -1,24 +1,32 @@
 use std::error::Error;
 
+use swissmilk::pairing::Pairing;
 use swissmilk::pairing_system::PairingSystemType;
 use swissmilk::player::Player;
-use swissmilk::random::NewRandom as _;
+use swissmilk::random::{NewRandom as _, Rng};
 use swissmilk::tournament::Tournament;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut tournament = Tournament::new(8, PairingSystemType::BergerTable);
+    let mut tournament = Tournament::new(9, PairingSystemType::BergerTable);
+    let mut rng = Rng::from_time();
     for _ in 0..9 {
-        tournament.add_player(Player::new_random());
+        tournament.add_player(Player::new_random(&mut rng));
     }
+    tournament.draw_lots(&mut rng);
 
     println!("{}", tournament.as_string_csv_like()?);
 
     let pairings = tournament.pair()?;
     for pair in pairings {
-        println!(
-            "{:?} VS {:?}. ({:?} vs {:?})",
-            pair.left, pair.right, pair.color_left, pair.color_right
-        );
+        match pair {
+            Pairing::Game {
+                left,
+                right,
+                color_left,
+                color_right,
+            } => println!("{left:?} VS {right:?}. ({color_left:?} vs {color_right:?})"),
+            Pairing::Bye(id) => println!("{id:?} has a bye"),
+        }
     }
 
     Ok(())
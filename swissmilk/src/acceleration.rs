@@ -0,0 +1,102 @@
+use crate::player::Player;
+use crate::tournament::Tournament;
+
+// Virtual points added to the top players' scores for pairing the first rounds, standings
+// always use the real scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Acceleration {
+    #[default]
+    None,
+    // FIDE C.04.5.1
+    Baku,
+}
+
+impl Acceleration {
+    // Virtual points of `player` when pairing `round`, in the tournament's result score units
+    #[must_use]
+    pub fn virtual_points(self, tournament: &Tournament, player: &Player, round: u8) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Baku => Self::baku(tournament, player, round),
+        }
+    }
+
+    // Group A is the top 2 * ceil(n / 4) of the starting rank, n counting every registered
+    // player. It gets a win in the first half of the accelerated rounds, a draw in the second
+    // half, the accelerated rounds being the first half of the tournament, halves rounded up.
+    fn baku(tournament: &Tournament, player: &Player, round: u8) -> u8 {
+        let player_count = tournament.player_count();
+        let group_a = 2 * player_count.div_ceil(4);
+        let rank = usize::from(player.pairing_number);
+        if rank == 0 || rank > group_a {
+            return 0;
+        }
+
+        let accelerated = tournament.number_of_rounds().div_ceil(2);
+        let (win, draw, _) = tournament.result_scores();
+
+        if round <= accelerated.div_ceil(2) {
+            win
+        } else if round <= accelerated {
+            draw
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Acceleration;
+    use crate::chess_types::{ByeValue, Title};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::Player;
+    use crate::tournament::Tournament;
+
+    fn tournament(result_scores: (u8, u8, u8)) -> Tournament {
+        let mut tournament =
+            Tournament::new_with_result_scores(8, PairingSystemType::ClassicSwiss, result_scores);
+        for id in 1..=9 {
+            let rating = 2500 - 50 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        tournament.set_acceleration(Acceleration::Baku);
+        tournament.start().unwrap();
+        tournament
+    }
+
+    // Group A of nine players is the top six, also when one of them sits the round out
+    #[test]
+    fn group_a_counts_the_whole_field() {
+        let mut tournament = tournament((2, 1, 0));
+        tournament.request_bye(9, 1, ByeValue::Loss).unwrap();
+
+        let pairings = tournament.pair().unwrap();
+        let opponent = |id| {
+            pairings
+                .iter()
+                .find_map(|x| if x.left() == id { x.right() } else { None })
+        };
+
+        assert_eq!(opponent(1), Some(4));
+        assert_eq!(opponent(3), Some(6));
+        assert_eq!(opponent(7), Some(8));
+    }
+
+    #[test]
+    fn virtual_draw_uses_the_draw_score() {
+        let tournament = tournament((3, 1, 0));
+        let top = tournament.get_player(1).unwrap();
+        let bottom = tournament.get_player(9).unwrap();
+        let points = |player, round| Acceleration::Baku.virtual_points(&tournament, player, round);
+
+        assert_eq!([1, 2, 3, 4, 5].map(|x| points(top, x)), [3, 3, 1, 1, 0]);
+        assert_eq!(points(bottom, 1), 0);
+    }
+}
@@ -1,84 +1,299 @@
+use std::collections::BTreeSet;
 use std::error::Error;
-use std::mem;
 
 use itertools::Itertools as _;
 
 use crate::chess_types::Color;
 use crate::pairing::Pairing;
 use crate::pairing_system::PairngSystem;
-use crate::player::Player;
+use crate::player::PlayerId;
 use crate::tournament::Tournament;
 
 pub(crate) struct BergerTablePairingSystem;
 
+// A board of a Berger table as published by FIDE (C.05 Annex 1), the white player first. Table
+// numbers start from 1, the top pairing number gets 1.
+pub type BergerBoard = (usize, usize);
+
 impl PairngSystem for BergerTablePairingSystem {
-    // TODO: Tests for BergerTablePairingSystem::pair_round
-    fn pair_round(tournament: &mut Tournament) -> Result<Vec<Pairing>, Box<dyn Error>> {
-        if tournament.has_started() {
-            tournament.start();
+    fn pair_round(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        if !tournament.has_started() {
+            tournament.start()?;
         }
 
-        let round = tournament.current_round();
+        if tournament.current_round() > tournament.number_of_rounds() {
+            return Err(format!(
+                "The round-robin is over after round {}",
+                tournament.number_of_rounds()
+            )
+            .into());
+        }
 
-        // PERF: Collect shouldn't be that slow because references
+        // An odd field plays the table of the next even size, meeting the last number is a bye
         let mut players = tournament
             .get_players()
-            .iter()
-            .sorted_by_key(|(_id, player)| player.pairing_number)
+            .values()
+            .filter(|player| available.contains(&player.info.id))
+            .sorted_by_key(|player| player.pairing_number)
+            .map(|player| Some(player.info.id))
             .collect::<Vec<_>>();
-        let mut player_count = tournament.player_count();
+        let field = players.len();
+        if field % 2 != 0 {
+            players.push(None);
+        }
 
-        let bye_player = Player::default();
-        if player_count % 2 != 0 {
-            player_count += 1;
-            players.push((&0, &bye_player));
+        let player_count = players.len();
+        if player_count < 2 {
+            return Err("A round-robin needs at least two players".into());
         }
 
-        let fixed = players[0];
-        let mut rest = players[1..].to_vec();
+        // Every cycle is a full round-robin, the odd ones (second, fourth...) with colours reversed
+        let cycle_length = player_count - 1;
+        let cycles = usize::from(tournament.cycles());
+        if usize::from(tournament.number_of_rounds()) != cycle_length * cycles {
+            return Err(format!(
+                "A round-robin of {} players over {cycles} cycle(s) takes {} rounds, not {}",
+                field,
+                cycle_length * cycles,
+                tournament.number_of_rounds()
+            )
+            .into());
+        }
 
-        let k_coeff = usize::from(round - 1) % (player_count - 1);
-        rest.rotate_left(k_coeff);
+        let index = usize::from(tournament.current_round() - 1);
+        let (cycle, mut round) = (index / cycle_length, index % cycle_length + 1);
+        if tournament.swap_last_rounds()
+            && cycle + 1 < cycles
+            && cycle_length > 1
+            && round + 1 >= cycle_length
+        {
+            round = if round == cycle_length {
+                round - 1
+            } else {
+                round + 1
+            };
+        }
 
-        let mut full = Vec::with_capacity(player_count);
-        full.push(fixed);
-        full.extend(rest);
+        let pairings = berger_round(player_count, round)
+            .into_iter()
+            .map(|(white, black)| {
+                let (white, black) = if cycle % 2 == 1 {
+                    (black, white)
+                } else {
+                    (white, black)
+                };
 
-        drop(players);
+                match (players[white - 1], players[black - 1]) {
+                    (Some(white), Some(black)) => {
+                        Pairing::new(white, black, Color::White, Color::Black)
+                    }
+                    (Some(id), None) | (None, Some(id)) => Pairing::bye(id),
+                    (None, None) => unreachable!(),
+                }
+            })
+            .collect();
 
-        let mut pairings = Vec::with_capacity(player_count >> 1);
-        for i in 0..(player_count >> 1) {
-            let (mut left, mut right) = (Some(full[i]), Some(full[player_count - 1 - i]));
+        Ok(pairings)
+    }
+}
+
+// The Berger table for `player_count` players, an odd count gets the table of the next even size
+#[must_use]
+pub fn berger_table(player_count: usize) -> Vec<Vec<BergerBoard>> {
+    let player_count = player_count + player_count % 2;
+    (1..player_count)
+        .map(|round| berger_round(player_count, round))
+        .collect()
+}
 
-            let mut left_color = Color::White;
-            let mut right_color = Color::Black;
+// Board 1 is the game of the last table number, who alternates colours and meets 1, n/2 + 1, 2,
+// n/2 + 2... The other boards pair the players around that opponent, the lower number getting
+// white when the sum of the two is odd.
+fn berger_round(player_count: usize, round: usize) -> Vec<BergerBoard> {
+    let cycle_length = player_count - 1;
+    let facing_last = if round % 2 == 1 {
+        round.div_ceil(2)
+    } else {
+        (player_count + round) >> 1
+    };
 
-            if round % 2 == 0 {
-                mem::swap(&mut left_color, &mut right_color);
-            }
+    let mut boards = Vec::with_capacity(player_count >> 1);
+    boards.push(if round % 2 == 1 {
+        (facing_last, player_count)
+    } else {
+        (player_count, facing_last)
+    });
+
+    for k in 1..player_count >> 1 {
+        let a = (facing_last + k - 1) % cycle_length + 1;
+        let b = (facing_last + cycle_length - k - 1) % cycle_length + 1;
 
-            // SAFETY: Always Some
-            unsafe {
-                if *left.unwrap_unchecked().0 == 0 {
-                    mem::swap(&mut left, &mut right);
+        boards.push(if (a + b) % 2 == 1 {
+            (a.min(b), a.max(b))
+        } else {
+            (a.max(b), a.min(b))
+        });
+    }
+
+    boards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BergerBoard, berger_table};
+    use crate::chess_types::{Color, GameResult, Title};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::Player;
+    use crate::random::Rng;
+    use crate::tournament::Tournament;
+
+    // C.05 Annex 1
+    const FOUR: [[BergerBoard; 2]; 3] = [[(1, 4), (2, 3)], [(4, 3), (1, 2)], [(2, 4), (3, 1)]];
+    const SIX: [[BergerBoard; 3]; 5] = [
+        [(1, 6), (2, 5), (3, 4)],
+        [(6, 4), (5, 3), (1, 2)],
+        [(2, 6), (3, 1), (4, 5)],
+        [(6, 5), (1, 4), (2, 3)],
+        [(3, 6), (4, 2), (5, 1)],
+    ];
+    const EIGHT: [[BergerBoard; 4]; 7] = [
+        [(1, 8), (2, 7), (3, 6), (4, 5)],
+        [(8, 5), (6, 4), (7, 3), (1, 2)],
+        [(2, 8), (3, 1), (4, 7), (5, 6)],
+        [(8, 6), (7, 5), (1, 4), (2, 3)],
+        [(3, 8), (4, 2), (5, 1), (6, 7)],
+        [(8, 7), (1, 6), (2, 5), (3, 4)],
+        [(4, 8), (5, 3), (6, 2), (7, 1)],
+    ];
+
+    #[test]
+    fn published_tables() {
+        assert_eq!(berger_table(4), FOUR.map(Vec::from));
+        assert_eq!(berger_table(3), FOUR.map(Vec::from));
+        assert_eq!(berger_table(6), SIX.map(Vec::from));
+        assert_eq!(berger_table(8), EIGHT.map(Vec::from));
+    }
 
-                    right = None;
-                    right_color = Color::None;
-                } else if *right.unwrap_unchecked().0 == 0 {
-                    right = None;
-                    right_color = Color::None;
+    #[test]
+    fn everyone_meets_once_with_balanced_colours() {
+        for player_count in (4..=20).step_by(2) {
+            let mut games = vec![vec![0; player_count + 1]; player_count + 1];
+            let mut whites = vec![0; player_count + 1];
+
+            for round in berger_table(player_count) {
+                assert_eq!(round.len(), player_count / 2);
+                for (white, black) in round {
+                    games[white][black] += 1;
+                    games[black][white] += 1;
+                    whites[white] += 1;
                 }
             }
 
-            pairings.push(Pairing::new(
-                // SAFETY: Always Some
-                unsafe { *left.unwrap_unchecked().0 },
-                right.map(|x| *x.0),
-                left_color,
-                right_color,
-            ));
+            for a in 1..=player_count {
+                for b in (1..=player_count).filter(|&b| b != a) {
+                    assert_eq!(games[a][b], 1, "{a} and {b} with {player_count} players");
+                }
+
+                let half = player_count / 2;
+                assert!(
+                    (half - 1..=half).contains(&whites[a]),
+                    "{a} with {player_count} players"
+                );
+            }
         }
+    }
 
-        Ok(pairings)
+    #[test]
+    fn pairing_numbers_are_drawn_and_rounds_run_out() {
+        let mut tournament = Tournament::new(3, PairingSystemType::BergerTable);
+        for id in 1..=4 {
+            tournament.add_player(Player::new(id, &format!("Player {id}"), Title::None, 2000));
+        }
+
+        // Nothing is drawn behind the arbiter's back
+        assert!(tournament.pair().is_err());
+        assert!(!tournament.has_started());
+
+        tournament.draw_lots(&mut Rng::new(11));
+        for round in FOUR {
+            let pairings = tournament.pair().unwrap();
+            let number = |id| tournament.get_player(id).unwrap().pairing_number as usize;
+
+            for (pairing, (white, black)) in pairings.iter().zip(round) {
+                let (white_id, black_id) = pairing.white_and_black().unwrap();
+                assert_eq!(pairing.colors().unwrap().0, Color::White);
+                assert_eq!((number(white_id), number(black_id)), (white, black));
+            }
+
+            for pairing in &pairings {
+                tournament.submit_result(pairing, GameResult::Draw).unwrap();
+            }
+            tournament.close_round().unwrap();
+        }
+
+        assert!(tournament.pair().is_err());
+    }
+
+    // Every round of a four player event, pairing numbers of white and black board by board
+    fn play(cycles: u8, swap_last_rounds: bool) -> Vec<Vec<BergerBoard>> {
+        let mut tournament = Tournament::new(3 * cycles, PairingSystemType::BergerTable);
+        for id in 1..=4 {
+            tournament.add_player(Player::new(id, &format!("Player {id}"), Title::None, 2000));
+        }
+        tournament.set_cycles(cycles);
+        tournament.set_swap_last_rounds(swap_last_rounds);
+        tournament.draw_lots(&mut Rng::new(5));
+
+        let mut rounds = Vec::new();
+        while let Ok(pairings) = tournament.pair() {
+            let number = |id| tournament.get_player(id).unwrap().pairing_number as usize;
+            rounds.push(
+                pairings
+                    .iter()
+                    .map(|x| {
+                        let (white, black) = x.white_and_black().unwrap();
+                        (number(white), number(black))
+                    })
+                    .collect(),
+            );
+
+            for pairing in &pairings {
+                tournament.submit_result(pairing, GameResult::Draw).unwrap();
+            }
+            tournament.close_round().unwrap();
+        }
+
+        rounds
+    }
+
+    #[test]
+    fn second_cycle_reverses_colours() {
+        let reversed = FOUR.map(|x| x.map(|(white, black)| (black, white)).to_vec());
+        let expected = FOUR
+            .map(Vec::from)
+            .into_iter()
+            .chain(reversed)
+            .collect::<Vec<_>>();
+
+        assert_eq!(play(2, false), expected);
+    }
+
+    // Without the swap, player 1 would get white in rounds 1 and 2, then black in rounds 3 and 4
+    #[test]
+    fn last_rounds_of_a_cycle_are_swapped() {
+        let reversed = FOUR.map(|x| x.map(|(white, black)| (black, white)).to_vec());
+        let [first, second, last] = FOUR.map(Vec::from);
+        let expected = [first, last, second]
+            .into_iter()
+            .chain(reversed)
+            .collect::<Vec<_>>();
+
+        assert_eq!(play(2, true), expected);
+
+        // The last cycle is played as published
+        assert_eq!(play(1, true), FOUR.map(Vec::from));
     }
 }
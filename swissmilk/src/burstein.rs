@@ -0,0 +1,462 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use itertools::Itertools as _;
+use smallvec::SmallVec;
+
+use crate::pairing::Pairing;
+use crate::pairing_system::PairngSystem;
+use crate::player::PlayerId;
+use crate::swiss::{self, SwissContext};
+use crate::tiebreak::{Tiebreak, TiebreakValue};
+use crate::tournament::Tournament;
+use crate::trace::{BracketOutcome, BracketTrace, PairingTrace, Shortfall, Tries};
+
+// Search steps per score group before giving up, floater sets included
+const MAX_GROUP_NODES: usize = 2_000_000;
+
+pub(crate) struct BursteinPairingSystem;
+
+// Pairs made inside a score group, and the players floating down from it
+type GroupPairing = (Vec<(usize, usize)>, Vec<usize>);
+
+// Compared in order: Buchholz, Sonneborn-Berger, Median Buchholz
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+struct Index {
+    buchholz: TiebreakValue,
+    sonneborn_berger: TiebreakValue,
+    median_buchholz: TiebreakValue,
+}
+
+impl PairngSystem for BursteinPairingSystem {
+    #[inline]
+    fn pair_round(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, None)
+    }
+
+    #[inline]
+    fn pair_round_traced(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: &mut PairingTrace,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, Some(trace))
+    }
+}
+
+impl BursteinPairingSystem {
+    // The first half of the event is paired by rating, like the Dutch system
+    fn seeding_rounds(tournament: &Tournament) -> u8 {
+        (tournament.number_of_rounds() >> 1).max(1)
+    }
+
+    fn pair(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        if !tournament.has_started() {
+            tournament.start()?;
+        }
+
+        if tournament.current_round() == 1 {
+            return Ok(swiss::pair_first_round(tournament, available, trace));
+        }
+
+        Self::pair_later_round(tournament, available, trace)
+    }
+
+    fn pair_later_round(
+        tournament: &Tournament,
+        available: &BTreeSet<PlayerId>,
+        mut trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        let context = SwissContext::new(tournament, available);
+        let is_seeding = tournament.current_round() <= Self::seeding_rounds(tournament);
+
+        let index = context
+            .entrants
+            .iter()
+            .map(|x| {
+                if is_seeding {
+                    Index::default()
+                } else {
+                    Self::index(tournament, x.player.info.id)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let everyone = (0..context.entrants.len()).collect::<Vec<_>>();
+        if !context.can_complete(&everyone) {
+            return Err("No pairing satisfies the absolute criteria".into());
+        }
+
+        let mut pairs = Vec::with_capacity(everyone.len() >> 1);
+        let mut bye = None;
+        let mut floaters = Vec::new();
+
+        for (i, bracket) in context.brackets.iter().enumerate() {
+            let is_last = i == context.brackets.len() - 1;
+
+            // Floaters stay on top, residents are ranked by index and then by pairing number
+            let residents = bracket
+                .clone()
+                .sorted_by_key(|&x| (Reverse(index[x]), x))
+                .collect::<Vec<_>>();
+            let downfloaters = std::mem::take(&mut floaters);
+            let group = [downfloaters.as_slice(), &residents].concat();
+            let rest = (bracket.end..context.entrants.len()).collect::<Vec<_>>();
+
+            let mut tries = Tries::default();
+            let (group_pairs, leftovers, outcome) =
+                match Self::pair_group(&context, &group, &rest, is_seeding, &mut tries)? {
+                    Some((pairs, leftovers)) => (pairs, leftovers, BracketOutcome::Paired),
+                    None if is_last => {
+                        let completion = context
+                            .find_completion(&group)
+                            .ok_or("Couldn't pair the last score group")?;
+
+                        let pairs = completion
+                            .iter()
+                            .filter_map(|&(a, b)| Some((a.min(b?), a.max(b?))));
+                        let leftovers = completion.iter().filter(|x| x.1.is_none()).map(|x| x.0);
+                        (
+                            pairs.collect(),
+                            leftovers.collect(),
+                            BracketOutcome::Completed,
+                        )
+                    }
+                    None => (Vec::new(), group, BracketOutcome::MovedDown),
+                };
+
+            if let Some(trace) = trace.as_deref_mut() {
+                let id = |x: usize| context.entrants[x].player.info.id;
+                let mut bracket_trace = BracketTrace {
+                    score: context.entrants[bracket.start].score,
+                    residents: context.ids(&residents),
+                    downfloaters: context.ids(&downfloaters),
+                    outcome,
+                    pairs: group_pairs.iter().map(|&(a, b)| (id(a), id(b))).collect(),
+                    floaters: context.ids(&leftovers),
+                    shortfalls: Shortfall::of(
+                        "Floaters",
+                        leftovers.len(),
+                        (downfloaters.len() + residents.len()) % 2,
+                    )
+                    .into_iter()
+                    .collect(),
+                    ..Default::default()
+                };
+                bracket_trace.set_tries(tries);
+                trace.brackets.push(bracket_trace);
+            }
+
+            pairs.extend(group_pairs);
+            if is_last {
+                bye = leftovers.first().copied();
+            } else {
+                floaters = leftovers;
+            }
+        }
+
+        let pairings =
+            context.to_pairings(pairs, bye, |high, low| context.allocate_colors(high, low));
+
+        if let Some(trace) = trace {
+            context.trace_boards(trace, &pairings, |high, low| {
+                context.allocate_colors_with_rule(high, low).2
+            });
+        }
+
+        Ok(pairings)
+    }
+
+    fn index(tournament: &Tournament, player_id: PlayerId) -> Index {
+        Index {
+            buchholz: Tiebreak::Buchholz.compute(tournament, player_id, &[]),
+            sonneborn_berger: Tiebreak::SonnebornBerger.compute(tournament, player_id, &[]),
+            median_buchholz: Tiebreak::MedianBuchholz.compute(tournament, player_id, &[]),
+        }
+    }
+
+    // Floaters are taken from the bottom of the group, as few as possible
+    fn pair_group(
+        context: &SwissContext,
+        group: &[usize],
+        rest: &[usize],
+        is_seeding: bool,
+        tries: &mut Tries,
+    ) -> Result<Option<GroupPairing>, Box<dyn Error>> {
+        let mut search = GroupSearch {
+            context,
+            is_seeding,
+            score: group.last().map_or(0, |&x| context.entrants[x].score),
+            visited: 0,
+            tries,
+        };
+
+        for leftovers in (group.len() % 2..=group.len()).step_by(2) {
+            if rest.is_empty() && leftovers > 1 {
+                break;
+            }
+
+            // The bottom players first, in every combination
+            for floaters in group.iter().rev().copied().combinations(leftovers) {
+                search.visit()?;
+
+                let is_complete = if rest.is_empty() {
+                    floaters.iter().all(|&x| context.can_receive_bye(x))
+                } else {
+                    let lower = floaters.iter().chain(rest).copied().collect::<Vec<_>>();
+                    context.can_complete(&lower)
+                };
+
+                if !is_complete {
+                    search.tries.incomplete += 1;
+                    continue;
+                }
+
+                let remaining = group
+                    .iter()
+                    .copied()
+                    .filter(|x| !floaters.contains(x))
+                    .collect::<Vec<_>>();
+                let mut pairs = Vec::with_capacity(remaining.len() >> 1);
+
+                if search.pair_remaining(
+                    &remaining,
+                    &mut vec![false; remaining.len()],
+                    &mut pairs,
+                )? {
+                    return Ok(Some((pairs, floaters)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+struct GroupSearch<'c, 'a, 't> {
+    context: &'c SwissContext<'a>,
+    is_seeding: bool,
+    // The score group being paired, and the search steps spent on it
+    score: u8,
+    visited: usize,
+    tries: &'t mut Tries,
+}
+
+impl GroupSearch<'_, '_, '_> {
+    fn visit(&mut self) -> Result<(), Box<dyn Error>> {
+        self.visited += 1;
+        if self.visited > MAX_GROUP_NODES {
+            return Err(format!(
+                "Gave up on the score {} group after {MAX_GROUP_NODES} search steps",
+                self.score
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    // During the seeding rounds the top half meets the bottom half, afterwards the highest
+    // index meets the lowest. Incompatible opponents are skipped in the same order.
+    fn pair_remaining(
+        &mut self,
+        group: &[usize],
+        used: &mut [bool],
+        pairs: &mut Vec<(usize, usize)>,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.visit()?;
+
+        let unpaired = (0..group.len())
+            .filter(|&i| !used[i])
+            .collect::<SmallVec<[usize; 32]>>();
+        let Some((&current, others)) = unpaired.split_first() else {
+            return Ok(true);
+        };
+
+        let partners = if self.is_seeding {
+            let half = unpaired.len() >> 1;
+            others[half - 1..]
+                .iter()
+                .chain(&others[..half - 1])
+                .copied()
+                .collect::<Vec<_>>()
+        } else {
+            others.iter().rev().copied().collect()
+        };
+
+        used[current] = true;
+
+        for partner in partners {
+            if !self.context.compatible(group[current], group[partner]) {
+                self.tries.incompatible += 1;
+                continue;
+            }
+
+            used[partner] = true;
+            let (a, b) = (group[current], group[partner]);
+            pairs.push((a.min(b), a.max(b)));
+
+            if self.pair_remaining(group, used, pairs)? {
+                return Ok(true);
+            }
+
+            pairs.pop();
+            used[partner] = false;
+            self.tries.transpositions += 1;
+        }
+
+        used[current] = false;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BursteinPairingSystem, Index};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::PlayerId;
+    use crate::tournament::Tournament;
+    use crate::trf;
+
+    const RATINGS: [u16; 8] = [2400, 2350, 2300, 2250, 2200, 2150, 2100, 2050];
+
+    // The white player of each board, then the black one
+    fn boards(tournament: &mut Tournament) -> Vec<(PlayerId, PlayerId)> {
+        tournament
+            .pair()
+            .unwrap()
+            .into_iter()
+            .filter_map(|x| x.white_and_black())
+            .collect()
+    }
+
+    fn has_pair(boards: &[(PlayerId, PlayerId)], a: PlayerId, b: PlayerId) -> bool {
+        boards.contains(&(a, b)) || boards.contains(&(b, a))
+    }
+
+    // Neighbours drew in round 1, so everyone is on 1 point with the same index
+    fn neighbours_drew(number_of_rounds: u8) -> Tournament {
+        trf::replay(
+            &RATINGS,
+            &[
+                &[(2, 'w', '=')],
+                &[(1, 'b', '=')],
+                &[(4, 'w', '=')],
+                &[(3, 'b', '=')],
+                &[(6, 'w', '=')],
+                &[(5, 'b', '=')],
+                &[(8, 'w', '=')],
+                &[(7, 'b', '=')],
+            ],
+            number_of_rounds,
+            PairingSystemType::BursteinSwiss,
+        )
+    }
+
+    #[test]
+    fn seeding_rounds_are_the_first_half() {
+        for (rounds, seeding) in [(1, 1), (2, 1), (5, 2), (7, 3), (9, 4), (10, 5), (11, 5)] {
+            let tournament = Tournament::new(rounds, PairingSystemType::BursteinSwiss);
+            assert_eq!(
+                BursteinPairingSystem::seeding_rounds(&tournament),
+                seeding,
+                "{rounds} rounds"
+            );
+        }
+    }
+
+    // Round 2 of 6 is a seeding round: the top half meets the bottom half
+    #[test]
+    fn seeding_rounds_pair_top_half_against_bottom_half() {
+        let boards = boards(&mut neighbours_drew(6));
+        for (a, b) in [(1, 5), (2, 6), (3, 7), (4, 8)] {
+            assert!(has_pair(&boards, a, b), "{boards:?}");
+        }
+    }
+
+    // Round 2 of 2 comes after the seeding rounds: the highest index meets the lowest, the
+    // pairing number breaking the ties
+    #[test]
+    fn index_rounds_pair_highest_against_lowest() {
+        let boards = boards(&mut neighbours_drew(2));
+        for (a, b) in [(1, 8), (2, 7), (3, 6), (4, 5)] {
+            assert!(has_pair(&boards, a, b), "{boards:?}");
+        }
+    }
+
+    #[test]
+    fn index_compares_buchholz_then_sonneborn_berger_then_median() {
+        let index = |buchholz, sonneborn_berger, median_buchholz| Index {
+            buchholz,
+            sonneborn_berger,
+            median_buchholz,
+        };
+
+        assert!(index(10, 0, 0) > index(9, 50, 50));
+        assert!(index(10, 5, 0) > index(10, 4, 50));
+        assert!(index(10, 5, 3) > index(10, 5, 2));
+    }
+
+    // After round 2, 2, 4, 6 and 8 share 1 point. Players 2 and 6 lost to the leaders, so have
+    // the higher Buchholz (5 against 3) and rank above 4, whatever the pairing numbers.
+    #[test]
+    fn residents_are_ranked_by_index() {
+        let mut tournament = trf::replay(
+            &RATINGS,
+            &[
+                &[(2, 'w', '1'), (3, 'b', '1')],
+                &[(1, 'b', '0'), (4, 'w', '=')],
+                &[(4, 'w', '1'), (1, 'w', '0')],
+                &[(3, 'b', '0'), (2, 'b', '=')],
+                &[(6, 'w', '1'), (7, 'w', '1')],
+                &[(5, 'b', '0'), (8, 'w', '=')],
+                &[(8, 'w', '1'), (5, 'b', '0')],
+                &[(7, 'b', '0'), (6, 'b', '=')],
+            ],
+            4,
+            PairingSystemType::BursteinSwiss,
+        );
+
+        let buchholz =
+            [2, 4, 6, 8].map(|id| BursteinPairingSystem::index(&tournament, id).buchholz);
+        assert_eq!(buchholz, [5, 3, 5, 3]);
+
+        // Ranked 2, 6, 4, 8
+        let boards = boards(&mut tournament);
+        assert!(has_pair(&boards, 2, 8), "{boards:?}");
+        assert!(has_pair(&boards, 6, 4), "{boards:?}");
+    }
+
+    // 1, 2 and 3 lead on 1.5 points and 1 drew 2. Floating 3, the bottom player, leaves 1 and 2
+    // to meet again, so 2 floats instead.
+    #[test]
+    fn the_lowest_floater_that_lets_the_group_pair() {
+        let mut tournament = trf::replay(
+            &RATINGS[..6],
+            &[
+                &[(4, 'w', '1'), (2, 'b', '=')],
+                &[(5, 'w', '1'), (1, 'w', '=')],
+                &[(6, 'w', '1'), (4, 'b', '=')],
+                &[(1, 'b', '0'), (3, 'w', '=')],
+                &[(2, 'b', '0'), (6, 'w', '=')],
+                &[(3, 'b', '0'), (5, 'b', '=')],
+            ],
+            4,
+            PairingSystemType::BursteinSwiss,
+        );
+
+        let (_, trace) = tournament.pair_with_trace().unwrap();
+        let leaders = &trace.brackets[0];
+        assert_eq!(leaders.residents, [1, 2, 3]);
+        assert_eq!(leaders.pairs, [(1, 3)]);
+        assert_eq!(leaders.floaters, [2]);
+    }
+}
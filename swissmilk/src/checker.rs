@@ -0,0 +1,630 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+
+use itertools::Itertools as _;
+
+use crate::chess_types::{Color, Float};
+use crate::dubov;
+use crate::dutch;
+use crate::pairing::Pairing;
+use crate::pairing_system::PairingSystemType;
+use crate::player::PlayerId;
+use crate::swiss::SwissContext;
+use crate::tournament::Tournament;
+
+// A pairing checker in the spirit of FIDE's FPC: every absolute and completion criterion the
+// proposed pairing breaks, and every quality criterion where the engine does better
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    UnknownPlayer(PlayerId),
+    // Withdrawn, not entered yet or with a requested bye
+    Unavailable(PlayerId),
+    NotPaired(PlayerId),
+    PairedTwice(PlayerId),
+    InvalidColors(PlayerId, PlayerId),
+    // C1
+    RepeatedGame(PlayerId, PlayerId),
+    // C2
+    SecondBye(PlayerId),
+    // C3
+    AbsoluteColorClash(PlayerId, PlayerId),
+    // C4, more than one player left unpaired, or one with an even field
+    TooManyByes(usize),
+    // Round-robins and Scheveningens have exactly one valid pairing per round, colours included
+    NotInTable(PlayerId),
+    Quality {
+        criterion: &'static str,
+        proposed: u32,
+        engine: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    pub violations: Vec<Violation>,
+    // What the engine pairs instead
+    pub engine_pairings: Vec<Pairing>,
+    pub engine_differs: bool,
+    pub engine_is_better: bool,
+}
+
+// A criterion over the whole round, lower is better: a count, or score differences from the
+// largest
+type Criterion = (&'static str, Vec<u32>);
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPlayer(a) => write!(f, "player {a} isn't in the tournament"),
+            Self::Unavailable(a) => write!(f, "player {a} isn't available for the round"),
+            Self::NotPaired(a) => write!(f, "player {a} isn't paired"),
+            Self::PairedTwice(a) => write!(f, "player {a} is paired more than once"),
+            Self::InvalidColors(a, b) => write!(f, "{a} - {b} don't get opposite colours"),
+            Self::RepeatedGame(a, b) => write!(f, "C1: {a} and {b} have already played"),
+            Self::SecondBye(a) => write!(f, "C2: player {a} already had a bye"),
+            Self::AbsoluteColorClash(a, b) => {
+                write!(
+                    f,
+                    "C3: {a} and {b} have the same absolute colour preference"
+                )
+            }
+            Self::TooManyByes(count) => write!(f, "C4: {count} players are left unpaired"),
+            Self::NotInTable(a) => write!(f, "player {a} isn't paired as the table says"),
+            Self::Quality {
+                criterion,
+                proposed,
+                engine,
+            } => write!(f, "{criterion}: {proposed}, the engine gets {engine}"),
+        }
+    }
+}
+
+impl CheckReport {
+    #[inline]
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.violations
+            .iter()
+            .all(|x| matches!(x, Violation::Quality { .. }))
+    }
+}
+
+/// Checks a pairing proposed for the next round of a started tournament, which is left as is
+///
+/// # Errors
+/// When the tournament hasn't started or the engine can't pair the round to compare against.
+pub fn check(tournament: &Tournament, pairings: &[Pairing]) -> Result<CheckReport, Box<dyn Error>> {
+    if !tournament.has_started() {
+        return Err("The tournament hasn't started, there are no pairing numbers yet".into());
+    }
+
+    // The engine pairs a copy, pairing needs the tournament mutably
+    let engine_pairings = tournament.clone().generate_pairings()?;
+    let engine_differs = normalize(pairings) != normalize(&engine_pairings);
+
+    let mut violations = presence_violations(tournament, pairings);
+
+    let better_quality = match tournament.get_pairing_system_type() {
+        PairingSystemType::BergerTable | PairingSystemType::Scheveningen => {
+            violations.extend(table_violations(pairings, &engine_pairings));
+            false
+        }
+        PairingSystemType::ClassicSwiss => {
+            violations.extend(swiss_violations(tournament, pairings));
+            dutch_violations(tournament, pairings, &engine_pairings, &mut violations)?
+        }
+        system => {
+            violations.extend(swiss_violations(tournament, pairings));
+            criteria_violations(
+                tournament,
+                system,
+                pairings,
+                &engine_pairings,
+                &mut violations,
+            )
+        }
+    };
+
+    // A differing pairing is worse when it breaks a rule or the engine does better on the
+    // system's criteria
+    let engine_is_better = engine_differs
+        && (better_quality
+            || violations
+                .iter()
+                .any(|x| !matches!(x, Violation::Quality { .. })));
+
+    Ok(CheckReport {
+        violations,
+        engine_pairings,
+        engine_differs,
+        engine_is_better,
+    })
+}
+
+// White first, so colours count
+#[inline]
+fn key(pairing: &Pairing) -> (PlayerId, Option<PlayerId>) {
+    pairing
+        .white_and_black()
+        .map_or((pairing.left(), None), |(white, black)| {
+            (white, Some(black))
+        })
+}
+
+fn normalize(pairings: &[Pairing]) -> BTreeSet<(PlayerId, Option<PlayerId>)> {
+    pairings.iter().map(key).collect()
+}
+
+// Everyone who should play is paired once, no one else is, and every game has both colours
+fn presence_violations(tournament: &Tournament, pairings: &[Pairing]) -> Vec<Violation> {
+    let round = tournament.current_round();
+    let should_play =
+        |id| tournament.is_available(id, round) || tournament.keeps_table_place(id, round);
+
+    let mut violations = Vec::new();
+    let mut seen = BTreeSet::new();
+
+    for id in pairings
+        .iter()
+        .flat_map(|x| [Some(x.left()), x.right()])
+        .flatten()
+    {
+        if tournament.get_player(id).is_none() {
+            violations.push(Violation::UnknownPlayer(id));
+        } else if !seen.insert(id) {
+            violations.push(Violation::PairedTwice(id));
+        } else if !should_play(id) {
+            violations.push(Violation::Unavailable(id));
+        }
+    }
+
+    for &id in tournament.get_players().keys() {
+        if should_play(id) && !seen.contains(&id) {
+            violations.push(Violation::NotPaired(id));
+        }
+    }
+
+    for pairing in pairings {
+        let Pairing::Game {
+            left,
+            right,
+            color_left,
+            color_right,
+        } = *pairing
+        else {
+            continue;
+        };
+
+        if color_left == Color::None || color_left != !color_right {
+            violations.push(Violation::InvalidColors(left, right));
+        }
+    }
+
+    violations
+}
+
+// Round-robins and Scheveningens may repeat games over several cycles, only the table counts
+fn table_violations(pairings: &[Pairing], engine_pairings: &[Pairing]) -> Vec<Violation> {
+    let expected = normalize(engine_pairings);
+
+    pairings
+        .iter()
+        .filter(|x| !expected.contains(&key(x)))
+        .map(|x| Violation::NotInTable(x.left()))
+        .collect()
+}
+
+// C1 to C4, shared by every Swiss system
+fn swiss_violations(tournament: &Tournament, pairings: &[Pairing]) -> Vec<Violation> {
+    // Unavailable players are in too, pairing them is reported once, by `presence_violations`
+    let everyone = tournament.get_players().keys().copied().collect();
+    let context = SwissContext::new(tournament, &everyone);
+    let index = context
+        .entrants
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.player.info.id, i))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut violations = Vec::new();
+
+    let round = tournament.current_round();
+    let pool = index
+        .keys()
+        .filter(|&&x| tournament.is_available(x, round))
+        .count();
+
+    let byes = pairings.iter().filter(|x| x.is_bye()).collect::<Vec<_>>();
+    if byes.len() > pool % 2 {
+        violations.push(Violation::TooManyByes(byes.len()));
+    }
+
+    for bye in byes {
+        if index
+            .get(&bye.left())
+            .is_some_and(|&x| !context.can_receive_bye(x))
+        {
+            violations.push(Violation::SecondBye(bye.left()));
+        }
+    }
+
+    for pairing in pairings {
+        let Pairing::Game { left, right, .. } = *pairing else {
+            continue;
+        };
+
+        let (Some(&a), Some(&b)) = (index.get(&left), index.get(&right)) else {
+            continue;
+        };
+
+        if context.entrants[a]
+            .player
+            .previous_opponents
+            .contains(&right)
+        {
+            violations.push(Violation::RepeatedGame(left, right));
+        } else if !context.compatible(a, b) {
+            violations.push(Violation::AbsoluteColorClash(left, right));
+        }
+    }
+
+    violations
+}
+
+// Pairs as indices into the context, the higher ranked player first. Players the context
+// doesn't know are left out.
+fn index_pairs(context: &SwissContext, pairings: &[Pairing]) -> Vec<(usize, usize)> {
+    let index = context
+        .entrants
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.player.info.id, i))
+        .collect::<BTreeMap<_, _>>();
+
+    pairings
+        .iter()
+        .filter_map(|x| {
+            let (&a, &b) = (index.get(&x.left())?, index.get(&x.right()?)?);
+            Some((a.min(b), a.max(b)))
+        })
+        .collect()
+}
+
+// The engine's own quality (C5-C19), bracket by bracket from the top. Adds the criteria of the
+// first differing bracket where the engine does better, returns whether it does.
+fn dutch_violations(
+    tournament: &Tournament,
+    pairings: &[Pairing],
+    engine_pairings: &[Pairing],
+    violations: &mut Vec<Violation>,
+) -> Result<bool, Box<dyn Error>> {
+    let available = tournament.paired_players(tournament.current_round());
+    let context = SwissContext::new(tournament, &available);
+    let proposed = dutch::round_quality(&context, &index_pairs(&context, pairings))?;
+    let engine = dutch::round_quality(&context, &index_pairs(&context, engine_pairings))?;
+
+    let Some((proposed, engine)) = proposed.iter().zip(&engine).find(|(a, b)| a != b) else {
+        return Ok(false);
+    };
+    if proposed < engine {
+        return Ok(false);
+    }
+
+    violations.extend(
+        proposed
+            .shortfalls(engine)
+            .into_iter()
+            .map(|x| Violation::Quality {
+                criterion: x.criterion,
+                proposed: x.chosen,
+                engine: x.ideal,
+            }),
+    );
+    Ok(true)
+}
+
+// Adds every criterion of the other Swiss systems the engine does better on, returns whether the
+// engine's pairing is better overall
+fn criteria_violations(
+    tournament: &Tournament,
+    system: PairingSystemType,
+    pairings: &[Pairing],
+    engine_pairings: &[Pairing],
+    violations: &mut Vec<Violation>,
+) -> bool {
+    let available = tournament.paired_players(tournament.current_round());
+    let context = SwissContext::new(tournament, &available);
+    let proposed = criteria(
+        tournament,
+        system,
+        &context,
+        &index_pairs(&context, pairings),
+    );
+    let engine = criteria(
+        tournament,
+        system,
+        &context,
+        &index_pairs(&context, engine_pairings),
+    );
+
+    let values = |x: &[Criterion]| x.iter().map(|x| x.1.clone()).collect::<Vec<_>>();
+    if values(&engine) >= values(&proposed) {
+        return false;
+    }
+
+    let sum = |x: &[u32]| x.iter().sum::<u32>();
+    for ((criterion, proposed), (_, engine)) in proposed.iter().zip(&engine) {
+        if proposed > engine {
+            violations.push(Violation::Quality {
+                criterion,
+                proposed: sum(proposed),
+                engine: sum(engine),
+            });
+        }
+    }
+
+    true
+}
+
+// What Dubov, Burstein and Lim minimise over a round, in order. All of them float as few players
+// as possible, as close in score as possible. Dubov then avoids upfloating beyond MaxT or in
+// consecutive rounds and pairing two seekers of the same colour, Lim floating a player the same
+// way twice in a row.
+fn criteria(
+    tournament: &Tournament,
+    system: PairingSystemType,
+    context: &SwissContext,
+    pairs: &[(usize, usize)],
+) -> Vec<Criterion> {
+    let pairs = pairs
+        .iter()
+        .map(|&(high, low)| (&context.entrants[high], &context.entrants[low]))
+        .collect::<Vec<_>>();
+    let floats = pairs
+        .iter()
+        .filter(|(high, low)| high.score != low.score)
+        .collect::<Vec<_>>();
+
+    let count = |x: usize| vec![x as u32];
+    let differences = floats
+        .iter()
+        .map(|(high, low)| u32::from(high.score - low.score))
+        .sorted_unstable_by(|a, b| b.cmp(a))
+        .collect();
+
+    match system {
+        PairingSystemType::DubovSwiss => {
+            let max_upfloats = dubov::max_upfloats(tournament);
+            let seeking_alike = pairs.iter().filter(|(a, b)| {
+                a.preference.color() != Color::None && a.preference.color() == b.preference.color()
+            });
+
+            vec![
+                ("Upfloaters", count(floats.len())),
+                ("Upfloater score differences", differences),
+                (
+                    "Upfloaters beyond MaxT",
+                    count(
+                        floats
+                            .iter()
+                            .filter(|(_, low)| low.player.upfloats >= max_upfloats)
+                            .count(),
+                    ),
+                ),
+                (
+                    "Upfloaters in consecutive rounds",
+                    count(
+                        floats
+                            .iter()
+                            .filter(|(_, low)| low.player.last_float(1) == Float::Up)
+                            .count(),
+                    ),
+                ),
+                ("Colour seekers paired alike", count(seeking_alike.count())),
+            ]
+        }
+        PairingSystemType::Lim => {
+            let repeated = floats.iter().map(|(high, low)| {
+                usize::from(high.player.last_float(1) == Float::Down)
+                    + usize::from(low.player.last_float(1) == Float::Up)
+            });
+            vec![
+                ("Floaters", count(floats.len())),
+                ("Floater score differences", differences),
+                ("Repeated floats", count(repeated.sum())),
+            ]
+        }
+        _ => vec![
+            ("Floaters", count(floats.len())),
+            ("Floater score differences", differences),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Violation, check};
+    use crate::chess_types::{Color, GameResult, Title};
+    use crate::pairing::Pairing;
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::Player;
+    use crate::random::Rng;
+    use crate::tournament::Tournament;
+    use crate::trf;
+
+    const RATINGS: [u16; 4] = [2400, 2300, 2200, 2100];
+
+    fn tournament(rounds: u8, system: PairingSystemType, players: u64) -> Tournament {
+        let mut tournament = Tournament::new(rounds, system);
+        for id in 1..=players {
+            tournament.add_player(Player::new(id, &format!("Player {id}"), Title::None, 2000));
+        }
+
+        tournament
+    }
+
+    // 1 and 2 won round 1, 3 and 4 lost
+    fn winners_and_losers(system: PairingSystemType) -> Tournament {
+        trf::replay(
+            &RATINGS,
+            &[
+                &[(3, 'w', '1')],
+                &[(4, 'b', '1')],
+                &[(1, 'b', '0')],
+                &[(2, 'w', '0')],
+            ],
+            5,
+            system,
+        )
+    }
+
+    // Everyone drew round 1, so 1 and 4 want black, 2 and 3 white
+    fn everyone_drew(system: PairingSystemType) -> Tournament {
+        trf::replay(
+            &RATINGS,
+            &[
+                &[(3, 'w', '=')],
+                &[(4, 'b', '=')],
+                &[(1, 'b', '=')],
+                &[(2, 'w', '=')],
+            ],
+            5,
+            system,
+        )
+    }
+
+    // 1 against 4 and 2 against 3, valid but never the engine's choice in these fixtures
+    fn crossed() -> [Pairing; 2] {
+        [
+            Pairing::new(1, 4, Color::White, Color::Black),
+            Pairing::new(2, 3, Color::White, Color::Black),
+        ]
+    }
+
+    fn assert_falls_short(tournament: &Tournament, criterion: &str) {
+        let report = check(tournament, &crossed()).unwrap();
+
+        assert!(report.is_valid(), "{:?}", report.violations);
+        assert!(report.engine_is_better);
+        assert!(
+            report.violations.iter().any(
+                |x| matches!(x, Violation::Quality { criterion: name, .. } if *name == criterion)
+            ),
+            "{:?}",
+            report.violations
+        );
+    }
+
+    fn play_round(tournament: &mut Tournament) {
+        for pairing in tournament.pair().unwrap() {
+            if !pairing.is_bye() {
+                tournament
+                    .submit_result(&pairing, GameResult::Draw)
+                    .unwrap();
+            }
+        }
+
+        tournament.close_round().unwrap();
+    }
+
+    #[test]
+    fn second_cycle_of_a_round_robin() {
+        let mut tournament = tournament(6, PairingSystemType::BergerTable, 4);
+        tournament.set_cycles(2);
+        tournament.draw_lots(&mut Rng::new(3));
+        tournament.start().unwrap();
+
+        for _ in 0..3 {
+            play_round(&mut tournament);
+        }
+
+        let before = tournament.clone();
+        let pairings = tournament.clone().pair().unwrap();
+        let report = check(&tournament, &pairings).unwrap();
+
+        assert!(report.violations.is_empty(), "{:?}", report.violations);
+        assert!(!report.engine_differs);
+        assert_eq!(tournament, before);
+
+        // The right opponents with the colours the other way round aren't the table's pairing
+        let swapped = pairings
+            .iter()
+            .map(|x| match *x {
+                Pairing::Game {
+                    left,
+                    right,
+                    color_left,
+                    color_right,
+                } => Pairing::new(left, right, color_right, color_left),
+                Pairing::Bye(id) => Pairing::bye(id),
+            })
+            .collect::<Vec<_>>();
+        let report = check(&tournament, &swapped).unwrap();
+
+        assert!(!report.is_valid());
+        assert!(report.engine_is_better);
+        assert!(
+            report
+                .violations
+                .iter()
+                .all(|x| matches!(x, Violation::NotInTable(_)))
+        );
+    }
+
+    #[test]
+    fn withdrawn_player() {
+        let mut tournament = tournament(5, PairingSystemType::ClassicSwiss, 6);
+        tournament.start().unwrap();
+        play_round(&mut tournament);
+        tournament.withdraw(6).unwrap();
+
+        let before = tournament.clone();
+        let mut pairings = tournament.clone().pair().unwrap();
+        assert!(check(&tournament, &pairings).unwrap().is_valid());
+        assert_eq!(tournament, before);
+
+        // The player left over plays the withdrawn player instead of getting the bye
+        let bye = pairings.iter_mut().find(|x| x.is_bye()).unwrap();
+        *bye = Pairing::new(bye.left(), 6, Color::White, Color::Black);
+
+        let report = check(&tournament, &pairings).unwrap();
+        assert!(!report.is_valid());
+        assert!(report.violations.contains(&Violation::Unavailable(6)));
+    }
+
+    #[test]
+    fn not_started() {
+        let tournament = tournament(5, PairingSystemType::ClassicSwiss, 6);
+        assert!(check(&tournament, &[]).is_err());
+        assert!(!tournament.has_started());
+    }
+
+    #[test]
+    fn needless_floaters_in_every_swiss_system() {
+        for (system, criterion) in [
+            (PairingSystemType::ClassicSwiss, "C5 floaters"),
+            (PairingSystemType::DubovSwiss, "Upfloaters"),
+            (PairingSystemType::BursteinSwiss, "Floaters"),
+            (PairingSystemType::Lim, "Floaters"),
+        ] {
+            assert_falls_short(&winners_and_losers(system), criterion);
+        }
+    }
+
+    #[test]
+    fn dutch_colour_preferences() {
+        assert_falls_short(
+            &everyone_drew(PairingSystemType::ClassicSwiss),
+            "C10 colour preferences",
+        );
+    }
+
+    #[test]
+    fn dubov_colour_seekers() {
+        assert_falls_short(
+            &everyone_drew(PairingSystemType::DubovSwiss),
+            "Colour seekers paired alike",
+        );
+    }
+}
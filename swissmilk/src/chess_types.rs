@@ -18,6 +18,14 @@ back_to_enum! {
     }
 }
 
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub enum Sex {
+    #[default]
+    Unknown,
+    Male,
+    Female,
+}
+
 back_to_enum! {
     #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
     pub enum Color {
@@ -39,6 +47,47 @@ back_to_enum! {
     }
 }
 
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub enum Float {
+    #[default]
+    None,
+    Down,
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum GameResult {
+    LeftWins,
+    Draw,
+    RightWins,
+
+    LeftWinsByForfeit,
+    RightWinsByForfeit,
+    DoubleForfeit,
+    // Played, but neither player scores (e.g. both flags fell without a claim)
+    ZeroPoints,
+    // Not finished yet, it scores nothing and doesn't count as played until it's resolved
+    Adjourned,
+    // A bye worth nothing, not a game
+    ZeroPointBye,
+}
+
+// What a pairing-allocated bye scores
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub enum ByeValue {
+    #[default]
+    Win,
+    Draw,
+    Loss,
+}
+
+// A side of a Scheveningen, team A gets the colours the table gives
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Team {
+    A,
+    B,
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
 pub struct ColorPreference {
     color: Color,
@@ -46,6 +95,41 @@ pub struct ColorPreference {
     width: u8,
 }
 
+impl Title {
+    #[inline]
+    #[must_use]
+    pub const fn abbreviation(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::WCM => "WCM",
+            Self::WFM => "WFM",
+            Self::CM => "CM",
+            Self::WIM => "WIM",
+            Self::FM => "FM",
+            Self::WGM => "WGM",
+            Self::IM => "IM",
+            Self::GM => "GM",
+        }
+    }
+
+    // Accepts the FIDE abbreviations, in either case
+    #[must_use]
+    pub fn from_abbreviation(abbreviation: &str) -> Option<Self> {
+        match abbreviation.trim().to_ascii_uppercase().as_str() {
+            "" => Some(Self::None),
+            "WCM" => Some(Self::WCM),
+            "WFM" => Some(Self::WFM),
+            "CM" => Some(Self::CM),
+            "WIM" => Some(Self::WIM),
+            "FM" => Some(Self::FM),
+            "WGM" => Some(Self::WGM),
+            "IM" => Some(Self::IM),
+            "GM" => Some(Self::GM),
+            _ => None,
+        }
+    }
+}
+
 impl Not for Color {
     type Output = Self;
 
@@ -61,6 +145,7 @@ impl Not for Color {
 
 impl ColorPreference {
     #[inline]
+    #[must_use]
     pub const fn new(color: Color, level: ColorPreferenceLevel) -> Self {
         Self {
             color,
@@ -70,6 +155,7 @@ impl ColorPreference {
     }
 
     #[inline]
+    #[must_use]
     pub const fn new_with_width(color: Color, level: ColorPreferenceLevel, width: u8) -> Self {
         Self {
             color,
@@ -77,8 +163,75 @@ impl ColorPreference {
             width,
         }
     }
+
+    #[inline]
+    #[must_use]
+    pub const fn color(&self) -> Color {
+        self.color
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn level(&self) -> ColorPreferenceLevel {
+        self.level
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn width(&self) -> u8 {
+        self.width
+    }
 }
 
 // Using u8 instead of f32 is so extra, but whatever
 // win score, draw score, lose score
 pub type ResultScoreConfig = (u8, u8, u8);
+
+impl GameResult {
+    // Points scored by the left and the right player
+    #[inline]
+    #[must_use]
+    pub const fn scores(self, (win, draw, lose): ResultScoreConfig) -> (u8, u8) {
+        match self {
+            Self::LeftWins | Self::LeftWinsByForfeit => (win, lose),
+            Self::Draw => (draw, draw),
+            Self::RightWins | Self::RightWinsByForfeit => (lose, win),
+            Self::DoubleForfeit | Self::ZeroPoints | Self::ZeroPointBye => (lose, lose),
+            Self::Adjourned => (0, 0),
+        }
+    }
+
+    // Forfeited games don't count as played, so the players keep their colours and may meet again.
+    // Neither do adjourned games until they're resolved.
+    #[inline]
+    #[must_use]
+    pub const fn is_played(self) -> bool {
+        !matches!(
+            self,
+            Self::LeftWinsByForfeit
+                | Self::RightWinsByForfeit
+                | Self::DoubleForfeit
+                | Self::Adjourned
+                | Self::ZeroPointBye
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_adjourned(self) -> bool {
+        matches!(self, Self::Adjourned)
+    }
+}
+
+impl ByeValue {
+    // The result of the bye game, the player being on the left
+    #[inline]
+    #[must_use]
+    pub const fn result(self) -> GameResult {
+        match self {
+            Self::Win => GameResult::LeftWins,
+            Self::Draw => GameResult::Draw,
+            Self::Loss => GameResult::ZeroPointBye,
+        }
+    }
+}
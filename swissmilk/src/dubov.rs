@@ -0,0 +1,926 @@
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use itertools::Itertools as _;
+
+use crate::chess_types::{Color, ColorPreferenceLevel, Float};
+use crate::pairing::Pairing;
+use crate::pairing_system::PairngSystem;
+use crate::player::PlayerId;
+use crate::swiss::{self, Entrant, SwissContext};
+use crate::tiebreak::{Tiebreak, TiebreakValue};
+use crate::tournament::Tournament;
+use crate::trace::{BracketOutcome, BracketTrace, ColorRule, PairingTrace, Shortfall, Tries};
+
+// Search steps per score group before giving up, transpositions and exchanges included
+const MAX_GROUP_NODES: usize = 2_000_000;
+
+pub(crate) struct DubovPairingSystem;
+
+// Pairs made inside a score group, and the players left unpaired
+type GroupPairing = (Vec<(usize, usize)>, Vec<usize>);
+
+impl PairngSystem for DubovPairingSystem {
+    #[inline]
+    fn pair_round(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, None)
+    }
+
+    #[inline]
+    fn pair_round_traced(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: &mut PairingTrace,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, Some(trace))
+    }
+}
+
+impl DubovPairingSystem {
+    fn pair(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        if !tournament.has_started() {
+            tournament.start()?;
+        }
+
+        if tournament.current_round() == 1 {
+            return Ok(swiss::pair_first_round(tournament, available, trace));
+        }
+
+        Self::pair_later_round(tournament, available, trace)
+    }
+
+    fn pair_later_round(
+        tournament: &Tournament,
+        available: &BTreeSet<PlayerId>,
+        mut trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        let context =
+            SwissContext::new_with_color_compatibility(tournament, available, colors_compatible);
+        let aro = context
+            .entrants
+            .iter()
+            .map(|x| Tiebreak::AverageRatingOfOpponents.compute(tournament, x.player.info.id, &[]))
+            .collect::<Vec<_>>();
+        let dubov = Dubov {
+            context: &context,
+            aro: &aro,
+            max_upfloats: max_upfloats(tournament),
+            score: Cell::new(0),
+            visited: Cell::new(0),
+            tries: Cell::new(Tries::default()),
+            split: Cell::default(),
+        };
+
+        let everyone = (0..context.entrants.len()).collect::<Vec<_>>();
+        if !context.can_complete(&everyone) {
+            return Err("No pairing satisfies the absolute criteria".into());
+        }
+
+        let mut groups = context
+            .brackets
+            .iter()
+            .map(|x| x.clone().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut pairs = Vec::with_capacity(everyone.len() >> 1);
+        let mut bye = None;
+        let mut carried = Vec::new();
+
+        for i in 0..groups.len() {
+            let mut group = std::mem::take(&mut carried);
+            let (downfloaters, residents) = (group.clone(), groups[i].clone());
+            group.append(&mut groups[i]);
+
+            let score = context.entrants[context.brackets[i].start].score;
+            dubov.score.set(score);
+            dubov.visited.set(0);
+            dubov.tries.set(Tries::default());
+            dubov.split.take();
+
+            let lower = &mut groups[i + 1..];
+            let is_last = lower.iter().all(Vec::is_empty);
+
+            let (group_pairs, leftovers, outcome) = if is_last {
+                if let Some((pairs, leftovers)) = dubov.pair_group(&group, &[], group.len() % 2)? {
+                    (pairs, leftovers, BracketOutcome::Paired)
+                } else {
+                    let completion = context
+                        .find_completion(&group)
+                        .ok_or("Couldn't pair the last score group")?;
+
+                    let pairs = completion.iter().filter_map(|&(a, b)| Some((a, b?)));
+                    let leftovers = completion.iter().filter(|x| x.1.is_none()).map(|x| x.0);
+                    (
+                        pairs.collect(),
+                        leftovers.collect(),
+                        BracketOutcome::Completed,
+                    )
+                }
+            } else {
+                let (pairs, leftovers) = dubov.pair_with_upfloater(&mut group, lower)?;
+                let outcome = if pairs.is_empty() {
+                    BracketOutcome::MovedDown
+                } else {
+                    BracketOutcome::Paired
+                };
+
+                (pairs, leftovers, outcome)
+            };
+
+            // A group whose only player floated up has nothing left to pair
+            if let Some(trace) = trace.as_deref_mut().filter(|_| !group.is_empty()) {
+                trace.brackets.push(dubov.group_trace(
+                    &group,
+                    (&downfloaters, &residents),
+                    (&group_pairs, &leftovers),
+                    outcome,
+                    is_last,
+                ));
+            }
+
+            pairs.extend(group_pairs);
+            if is_last {
+                bye = leftovers.first().copied();
+                break;
+            }
+
+            carried = leftovers;
+        }
+
+        let pairings = context.to_pairings(pairs, bye, |white_seeker, black_seeker| {
+            dubov.allocate_colors(white_seeker, black_seeker)
+        });
+
+        // The white seeker is on the left of the board
+        if let Some(trace) = trace {
+            context.trace_boards(trace, &pairings, |white_seeker, black_seeker| {
+                dubov
+                    .allocate_colors_with_rule(white_seeker, black_seeker)
+                    .2
+            });
+        }
+
+        Ok(pairings)
+    }
+}
+
+// MaxT grows by one every five rounds
+#[inline]
+pub(crate) const fn max_upfloats(tournament: &Tournament) -> u8 {
+    2 + tournament.number_of_rounds() / 5
+}
+
+// C3: players with the same absolute colour preference never meet, topscorers included
+fn colors_compatible(_: &SwissContext<'_>, a: &Entrant<'_>, b: &Entrant<'_>) -> bool {
+    a.preference.level() != ColorPreferenceLevel::Absolute
+        || b.preference.level() != ColorPreferenceLevel::Absolute
+        || a.preference.color() != b.preference.color()
+}
+
+struct Dubov<'c, 'a> {
+    context: &'c SwissContext<'a>,
+    aro: &'c [TiebreakValue],
+    // MaxT, upfloats a player may have before being upfloated is avoided
+    max_upfloats: u8,
+    // The score group being paired, and the search steps spent on it
+    score: Cell<u8>,
+    visited: Cell<usize>,
+    tries: Cell<Tries>,
+    // White and black seekers the group's pairing came from, unless any pairing had to do
+    split: Cell<(Vec<usize>, Vec<usize>)>,
+}
+
+impl Dubov<'_, '_> {
+    fn rating(&self, x: usize) -> u16 {
+        self.context.entrants[x].player.info.rating
+    }
+
+    fn visit(&self) -> Result<(), Box<dyn Error>> {
+        self.visited.set(self.visited.get() + 1);
+        if self.visited.get() > MAX_GROUP_NODES {
+            return Err(format!(
+                "Gave up on the score {} group after {MAX_GROUP_NODES} search steps",
+                self.score.get()
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    fn tried(&self, count: impl FnOnce(&mut Tries)) {
+        let mut tries = self.tries.get();
+        count(&mut tries);
+        self.tries.set(tries);
+    }
+
+    // Upfloaters are the players of the group who neither moved down nor reside in it
+    fn group_trace(
+        &self,
+        group: &[usize],
+        (downfloaters, residents): (&[usize], &[usize]),
+        (pairs, leftovers): (&[(usize, usize)], &[usize]),
+        outcome: BracketOutcome,
+        is_last: bool,
+    ) -> BracketTrace {
+        let context = self.context;
+        let upfloaters = group
+            .iter()
+            .copied()
+            .filter(|x| !downfloaters.contains(x) && !residents.contains(x))
+            .collect::<Vec<_>>();
+        let id = |x: usize| context.entrants[x].player.info.id;
+        let (white, black) = self.split.take();
+
+        let mut trace = BracketTrace {
+            score: self.score.get(),
+            residents: context.ids(residents),
+            downfloaters: context.ids(downfloaters),
+            upfloaters: context.ids(&upfloaters),
+            s1: context.ids(&white),
+            s2: context.ids(&black),
+            outcome,
+            pairs: pairs.iter().map(|&(a, b)| (id(a), id(b))).collect(),
+            floaters: context.ids(leftovers),
+            shortfalls: self.shortfalls(pairs, &upfloaters, leftovers, is_last),
+            ..Default::default()
+        };
+        trace.set_tries(self.tries.get());
+        trace
+    }
+
+    // Where a group's pairing falls short of the best conceivable one: anyone floating down
+    // but the bye, upfloaters beyond MaxT or upfloated in the previous round, and more pairs
+    // of two white or two black seekers than the preferences make inevitable
+    fn shortfalls(
+        &self,
+        pairs: &[(usize, usize)],
+        upfloaters: &[usize],
+        leftovers: &[usize],
+        is_last: bool,
+    ) -> Vec<Shortfall> {
+        let entrants = &self.context.entrants;
+        let upfloated = |x: &&usize| entrants[**x].player.last_float(1) == Float::Up;
+        let beyond = |x: &&usize| entrants[**x].player.upfloats >= self.max_upfloats;
+
+        let colors = pairs
+            .iter()
+            .flat_map(|&(a, b)| [a, b])
+            .map(|x| entrants[x].preference.color())
+            .counts();
+        let count = |color| colors.get(&color).copied().unwrap_or_default();
+        let (whites, blacks) = (count(Color::White), count(Color::Black));
+        let alike = pairs.iter().filter(|&&(a, b)| {
+            let color = entrants[a].preference.color();
+            color != Color::None && color == entrants[b].preference.color()
+        });
+
+        [
+            Shortfall::of(
+                "Floaters",
+                leftovers.len(),
+                if is_last { leftovers.len() % 2 } else { 0 },
+            ),
+            Shortfall::of(
+                "Upfloaters beyond MaxT",
+                upfloaters.iter().filter(beyond).count(),
+                0,
+            ),
+            Shortfall::of(
+                "Upfloaters in consecutive rounds",
+                upfloaters.iter().filter(upfloated).count(),
+                0,
+            ),
+            Shortfall::of(
+                "Colour seekers paired alike",
+                alike.count(),
+                whites.abs_diff(blacks).saturating_sub(count(Color::None)) >> 1,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    // An odd group takes an upfloater from the next group down, and whoever cannot be
+    // paired moves down to the next group
+    fn pair_with_upfloater(
+        &self,
+        group: &mut Vec<usize>,
+        lower: &mut [Vec<usize>],
+    ) -> Result<GroupPairing, Box<dyn Error>> {
+        let rest = lower.concat();
+
+        if group.len() % 2 == 1 {
+            let next = lower
+                .iter_mut()
+                .find(|x| !x.is_empty())
+                .expect("A lower group exists");
+
+            for upfloater in self.upfloater_candidates(group, next) {
+                group.push(upfloater);
+                let rest = rest
+                    .iter()
+                    .copied()
+                    .filter(|&x| x != upfloater)
+                    .collect::<Vec<_>>();
+
+                if let Some((pairs, _)) = self.pair_group(group, &rest, 0)? {
+                    next.retain(|&x| x != upfloater);
+                    return Ok((pairs, Vec::new()));
+                }
+
+                group.pop();
+            }
+        }
+
+        for leftovers in (group.len() % 2..=group.len()).step_by(2) {
+            if let Some(paired) = self.pair_group(group, &rest, leftovers)? {
+                return Ok(paired);
+            }
+        }
+
+        Ok((Vec::new(), group.clone()))
+    }
+
+    // Upfloaters come from the next group down. Avoided in order: going beyond MaxT upfloats, an
+    // upfloat in the previous round, the colour the group has too many of. Then the highest
+    // rating, then the lowest ARO.
+    fn upfloater_candidates(&self, group: &[usize], next: &[usize]) -> Vec<usize> {
+        let (white_seekers, black_seekers) = self.seekers(group);
+        let needed = if white_seekers.len() > black_seekers.len() {
+            Color::Black
+        } else {
+            Color::White
+        };
+
+        next.iter()
+            .copied()
+            .filter(|&x| group.iter().any(|&y| self.context.compatible(x, y)))
+            .sorted_by_key(|&x| {
+                let entrant = &self.context.entrants[x];
+                (
+                    entrant.player.upfloats >= self.max_upfloats,
+                    entrant.player.last_float(1) == Float::Up,
+                    entrant.preference.color() == !needed,
+                    Reverse(self.rating(x)),
+                    self.aro[x],
+                    x,
+                )
+            })
+            .collect()
+    }
+
+    // White seekers sorted by ascending ARO, black seekers by descending rating. Players
+    // without a preference seek white.
+    fn seekers(&self, group: &[usize]) -> (Vec<usize>, Vec<usize>) {
+        let (mut white, mut black): (Vec<_>, Vec<_>) = group
+            .iter()
+            .partition(|&&x| self.context.entrants[x].preference.color() != Color::Black);
+
+        // Equalise the two sides by moving the players with the weakest preference
+        let weakest = |side: &[usize]| {
+            side.iter()
+                .enumerate()
+                .min_by_key(|&(_, &x)| {
+                    let preference = self.context.entrants[x].preference;
+                    (
+                        preference.level(),
+                        preference.color() != Color::None,
+                        Reverse(x),
+                    )
+                })
+                .map(|(i, _)| i)
+        };
+
+        while white.len() > black.len() + 1 {
+            let i = weakest(&white).expect("White seekers can't be empty");
+            black.push(white.remove(i));
+        }
+
+        while black.len() > white.len() + 1 {
+            let i = weakest(&black).expect("Black seekers can't be empty");
+            white.push(black.remove(i));
+        }
+
+        self.sort_seekers(&mut white, &mut black);
+        (white, black)
+    }
+
+    fn sort_seekers(&self, white: &mut [usize], black: &mut [usize]) {
+        white.sort_by_key(|&x| (self.aro[x], Reverse(self.rating(x)), x));
+        black.sort_by_key(|&x| (Reverse(self.rating(x)), self.aro[x], x));
+    }
+
+    // Tries, in order: white seekers against black seekers (with transpositions of the black
+    // seekers), exchanges of one white and one black seeker, the lowest white seekers and the
+    // highest black seekers first, and finally any compatible pairing
+    fn pair_group(
+        &self,
+        group: &[usize],
+        rest: &[usize],
+        leftovers: usize,
+    ) -> Result<Option<GroupPairing>, Box<dyn Error>> {
+        let (white, black) = self.seekers(group);
+
+        let exchanges = (0..white.len())
+            .rev()
+            .cartesian_product(0..black.len())
+            .map(|(i, j)| {
+                let (mut white, mut black) = (white.clone(), black.clone());
+                std::mem::swap(&mut white[i], &mut black[j]);
+                self.sort_seekers(&mut white, &mut black);
+                (white, black)
+            });
+
+        let exchanges = exchanges.inspect(|_| self.tried(|x| x.exchanges += 1));
+        for (white, black) in std::iter::once((white.clone(), black.clone())).chain(exchanges) {
+            let mut search = GroupSearch::new(self, rest, leftovers);
+            if search.pair_sides(
+                &white,
+                &black,
+                &mut vec![false; black.len()],
+                &mut Vec::new(),
+            )? {
+                self.split.set((white, black));
+                return Ok(search.found);
+            }
+        }
+
+        let everyone = group
+            .iter()
+            .copied()
+            .sorted_by_key(|&x| (Reverse(self.rating(x)), x))
+            .collect::<Vec<_>>();
+
+        let mut search = GroupSearch::new(self, rest, leftovers);
+        if search.pair_any(&everyone, &mut vec![false; everyone.len()], &mut Vec::new())? {
+            return Ok(search.found);
+        }
+
+        Ok(None)
+    }
+
+    #[inline]
+    fn allocate_colors(&self, white_seeker: usize, black_seeker: usize) -> (Color, Color) {
+        let (white_color, black_color, _) =
+            self.allocate_colors_with_rule(white_seeker, black_seeker);
+        (white_color, black_color)
+    }
+
+    // Colour allocation, in order: both preferences, the stronger preference, alternating from
+    // the last round the two had different colours, the preference of the higher ranked player
+    // (score, then rating). Otherwise the white seeker gets white.
+    fn allocate_colors_with_rule(
+        &self,
+        white_seeker: usize,
+        black_seeker: usize,
+    ) -> (Color, Color, ColorRule) {
+        let (white_player, black_player) = (
+            &self.context.entrants[white_seeker],
+            &self.context.entrants[black_seeker],
+        );
+        let (white_pref, black_pref) = (white_player.preference, black_player.preference);
+
+        let granted = |color: Color| (color, !color);
+
+        match (white_pref.color(), black_pref.color()) {
+            (Color::None, Color::None) => {}
+            (Color::None, color) => {
+                let (black_color, white_color) = granted(color);
+                return (white_color, black_color, ColorRule::BothPreferences);
+            }
+            (color, other) if color != other => {
+                let (white_color, black_color) = granted(color);
+                return (white_color, black_color, ColorRule::BothPreferences);
+            }
+            _ => {}
+        }
+
+        if white_pref.level() != black_pref.level() {
+            let (white_color, black_color) = if white_pref.level() > black_pref.level() {
+                granted(white_pref.color())
+            } else {
+                let (black_color, white_color) = granted(black_pref.color());
+                (white_color, black_color)
+            };
+
+            return (white_color, black_color, ColorRule::StrongerPreference);
+        }
+
+        let histories = white_player
+            .player
+            .color_history
+            .iter()
+            .rev()
+            .zip(black_player.player.color_history.iter().rev());
+
+        for (&white_color, &black_color) in histories {
+            if white_color != black_color
+                && white_color != Color::None
+                && black_color != Color::None
+            {
+                return (!white_color, !black_color, ColorRule::ColorHistory);
+            }
+        }
+
+        // Equal preferences are for the same colour, or none at all
+        let rank = |x: &Entrant<'_>| (x.score, x.player.info.rating);
+        if white_pref.color() != Color::None {
+            let (white_color, black_color) = if rank(white_player) >= rank(black_player) {
+                granted(white_pref.color())
+            } else {
+                let (black_color, white_color) = granted(black_pref.color());
+                (white_color, black_color)
+            };
+
+            return (white_color, black_color, ColorRule::HigherRankedPreference);
+        }
+
+        (Color::White, Color::Black, ColorRule::WhiteSeeker)
+    }
+}
+
+struct GroupSearch<'d, 'c, 'a> {
+    dubov: &'d Dubov<'c, 'a>,
+    rest: &'d [usize],
+    leftovers: usize,
+    skipped: Vec<usize>,
+    found: Option<GroupPairing>,
+}
+
+impl<'d, 'c, 'a> GroupSearch<'d, 'c, 'a> {
+    const fn new(dubov: &'d Dubov<'c, 'a>, rest: &'d [usize], leftovers: usize) -> Self {
+        Self {
+            dubov,
+            rest,
+            leftovers,
+            skipped: Vec::new(),
+            found: None,
+        }
+    }
+
+    fn pair_sides(
+        &mut self,
+        white: &[usize],
+        black: &[usize],
+        used: &mut [bool],
+        pairs: &mut Vec<(usize, usize)>,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.dubov.visit()?;
+
+        let Some((&current, white)) = white.split_first() else {
+            let unpaired = black
+                .iter()
+                .zip(used.iter())
+                .filter(|(_, used)| !**used)
+                .map(|(x, _)| *x);
+
+            return Ok(self.accept(
+                pairs,
+                self.skipped.iter().copied().chain(unpaired).collect(),
+            ));
+        };
+
+        for i in 0..black.len() {
+            if used[i] {
+                continue;
+            }
+            if !self.dubov.context.compatible(current, black[i]) {
+                self.dubov.tried(|x| x.incompatible += 1);
+                continue;
+            }
+
+            used[i] = true;
+            pairs.push((current, black[i]));
+
+            if self.pair_sides(white, black, used, pairs)? {
+                return Ok(true);
+            }
+
+            pairs.pop();
+            used[i] = false;
+            self.dubov.tried(|x| x.transpositions += 1);
+        }
+
+        if self.skipped.len() < self.leftovers {
+            self.skipped.push(current);
+
+            if self.pair_sides(white, black, used, pairs)? {
+                return Ok(true);
+            }
+
+            self.skipped.pop();
+        }
+
+        Ok(false)
+    }
+
+    fn pair_any(
+        &mut self,
+        group: &[usize],
+        used: &mut [bool],
+        pairs: &mut Vec<(usize, usize)>,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.dubov.visit()?;
+
+        let Some(current) = (0..group.len()).find(|&i| !used[i]) else {
+            return Ok(self.accept(pairs, self.skipped.clone()));
+        };
+
+        used[current] = true;
+
+        for i in current + 1..group.len() {
+            if used[i] {
+                continue;
+            }
+            if !self.dubov.context.compatible(group[current], group[i]) {
+                self.dubov.tried(|x| x.incompatible += 1);
+                continue;
+            }
+
+            used[i] = true;
+            pairs.push((group[current], group[i]));
+
+            if self.pair_any(group, used, pairs)? {
+                return Ok(true);
+            }
+
+            pairs.pop();
+            used[i] = false;
+            self.dubov.tried(|x| x.transpositions += 1);
+        }
+
+        if self.skipped.len() < self.leftovers {
+            self.skipped.push(group[current]);
+
+            if self.pair_any(group, used, pairs)? {
+                return Ok(true);
+            }
+
+            self.skipped.pop();
+        }
+
+        used[current] = false;
+        Ok(false)
+    }
+
+    fn accept(&mut self, pairs: &[(usize, usize)], unpaired: Vec<usize>) -> bool {
+        if unpaired.len() != self.leftovers {
+            return false;
+        }
+
+        let context = self.dubov.context;
+        let is_complete = if self.rest.is_empty() {
+            unpaired.len() <= 1 && unpaired.iter().all(|&x| context.can_receive_bye(x))
+        } else {
+            let lower = unpaired
+                .iter()
+                .chain(self.rest)
+                .copied()
+                .collect::<Vec<_>>();
+            context.can_complete(&lower)
+        };
+
+        if is_complete {
+            self.found = Some((pairs.to_vec(), unpaired));
+        } else {
+            self.dubov.tried(|x| x.incomplete += 1);
+        }
+
+        is_complete
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::{Dubov, colors_compatible, max_upfloats};
+    use crate::chess_types::Color;
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::PlayerId;
+    use crate::swiss::SwissContext;
+    use crate::tiebreak::Tiebreak;
+    use crate::tournament::Tournament;
+    use crate::trace::Tries;
+    use crate::trf;
+
+    const RATINGS: [u16; 8] = [2400, 2350, 2300, 2250, 2200, 2150, 2100, 2050];
+
+    fn with_dubov<T>(tournament: &Tournament, f: impl FnOnce(&Dubov<'_, '_>) -> T) -> T {
+        let available = tournament.get_players().keys().copied().collect();
+        let context =
+            SwissContext::new_with_color_compatibility(tournament, &available, colors_compatible);
+        let aro = context
+            .entrants
+            .iter()
+            .map(|x| Tiebreak::AverageRatingOfOpponents.compute(tournament, x.player.info.id, &[]))
+            .collect::<Vec<_>>();
+
+        f(&Dubov {
+            context: &context,
+            aro: &aro,
+            max_upfloats: max_upfloats(tournament),
+            score: Cell::new(0),
+            visited: Cell::new(0),
+            tries: Cell::new(Tries::default()),
+            split: Cell::default(),
+        })
+    }
+
+    fn index(dubov: &Dubov<'_, '_>, id: PlayerId) -> usize {
+        dubov
+            .context
+            .entrants
+            .iter()
+            .position(|x| x.player.info.id == id)
+            .unwrap()
+    }
+
+    fn ids(dubov: &Dubov<'_, '_>, players: &[usize]) -> Vec<PlayerId> {
+        dubov.context.ids(players)
+    }
+
+    // The white player of each board, then the black one
+    fn boards(tournament: &mut Tournament) -> Vec<(PlayerId, Option<PlayerId>)> {
+        tournament
+            .pair()
+            .unwrap()
+            .into_iter()
+            .map(|x| {
+                x.white_and_black()
+                    .map_or((x.left(), None), |(white, black)| (white, Some(black)))
+            })
+            .collect()
+    }
+
+    // The top half beats the bottom half in round 1: 1, 2, 3 and 4 lead with 1 and 3 seeking
+    // black, 2 and 4 white
+    fn top_half_wins() -> Tournament {
+        trf::replay(
+            &RATINGS,
+            &[
+                &[(5, 'w', '1')],
+                &[(6, 'b', '1')],
+                &[(7, 'w', '1')],
+                &[(8, 'b', '1')],
+                &[(1, 'b', '0')],
+                &[(2, 'w', '0')],
+                &[(3, 'b', '0')],
+                &[(4, 'w', '0')],
+            ],
+            5,
+            PairingSystemType::DubovSwiss,
+        )
+    }
+
+    // Player 4 met the lowest rated opponent, so comes first among the white seekers
+    #[test]
+    fn white_seekers_go_by_ascending_aro() {
+        let mut tournament = top_half_wins();
+
+        with_dubov(&tournament, |dubov| {
+            let group = [1, 2, 3, 4].map(|x| index(dubov, x));
+            let (white, black) = dubov.seekers(&group);
+
+            assert_eq!(ids(dubov, &white), [4, 2]);
+            assert_eq!(ids(dubov, &black), [1, 3]);
+            assert_eq!(dubov.aro[index(dubov, 4)], 2050);
+            assert_eq!(dubov.aro[index(dubov, 2)], 2150);
+        });
+
+        let boards = boards(&mut tournament);
+        assert!(boards.contains(&(4, Some(1))), "{boards:?}");
+        assert!(boards.contains(&(2, Some(3))), "{boards:?}");
+    }
+
+    // Every winner had black, so all four seek white. The lowest ranked are moved to the black
+    // seekers, who then go by rating.
+    #[test]
+    fn seekers_are_split_evenly() {
+        let mut tournament = trf::replay(
+            &RATINGS,
+            &[
+                &[(5, 'w', '0')],
+                &[(6, 'b', '1')],
+                &[(7, 'w', '0')],
+                &[(8, 'b', '1')],
+                &[(1, 'b', '1')],
+                &[(2, 'w', '0')],
+                &[(3, 'b', '1')],
+                &[(4, 'w', '0')],
+            ],
+            5,
+            PairingSystemType::DubovSwiss,
+        );
+
+        with_dubov(&tournament, |dubov| {
+            let group = [2, 4, 5, 7].map(|x| index(dubov, x));
+            let (white, black) = dubov.seekers(&group);
+
+            assert_eq!(ids(dubov, &white), [4, 2]);
+            assert_eq!(ids(dubov, &black), [5, 7]);
+
+            // Both want white and alternated alike, the higher ranked player gets it
+            let (white_color, black_color, _) =
+                dubov.allocate_colors_with_rule(index(dubov, 4), index(dubov, 5));
+            assert_eq!((white_color, black_color), (Color::White, Color::Black));
+            let (white_color, black_color, _) =
+                dubov.allocate_colors_with_rule(index(dubov, 7), index(dubov, 2));
+            assert_eq!((white_color, black_color), (Color::Black, Color::White));
+        });
+
+        let boards = boards(&mut tournament);
+        assert!(boards.contains(&(4, Some(5))), "{boards:?}");
+        assert!(boards.contains(&(2, Some(7))), "{boards:?}");
+    }
+
+    // 1, 2 and 6 win, 1 had white and the others black
+    fn one_black_seeker_leads() -> Tournament {
+        trf::replay(
+            &RATINGS[..6],
+            &[
+                &[(4, 'w', '1')],
+                &[(5, 'b', '1')],
+                &[(6, 'w', '0')],
+                &[(1, 'b', '0')],
+                &[(2, 'w', '0')],
+                &[(3, 'b', '1')],
+            ],
+            5,
+            PairingSystemType::DubovSwiss,
+        )
+    }
+
+    // 1, 2 and 6 lead with one black seeker, so the upfloater should seek black: player 5 goes
+    // before the higher rated 4, who seeks white.
+    #[test]
+    fn upfloater_brings_the_needed_colour() {
+        let mut tournament = one_black_seeker_leads();
+
+        with_dubov(&tournament, |dubov| {
+            let group = [1, 2, 6].map(|x| index(dubov, x));
+            let next = [3, 4, 5].map(|x| index(dubov, x));
+
+            let candidates = dubov.upfloater_candidates(&group, &next);
+            assert_eq!(ids(dubov, &candidates), [3, 5, 4]);
+        });
+
+        // 3 floats up. The first white seeker takes the second black seeker, 6 having met 3.
+        let boards = boards(&mut tournament);
+        assert!(boards.contains(&(2, Some(3))), "{boards:?}");
+        assert!(boards.contains(&(6, Some(1))), "{boards:?}");
+    }
+
+    // MaxT is 2 + 5 / 5 = 3 upfloats over five rounds, a fourth is avoided even for the needed
+    // colour
+    #[test]
+    fn upfloats_beyond_max_t_come_last() {
+        let mut tournament = one_black_seeker_leads();
+
+        tournament.get_player_mut(3).unwrap().upfloats = 3;
+
+        with_dubov(&tournament, |dubov| {
+            let group = [1, 2, 6].map(|x| index(dubov, x));
+            let next = [3, 4, 5].map(|x| index(dubov, x));
+
+            let candidates = dubov.upfloater_candidates(&group, &next);
+            assert_eq!(ids(dubov, &candidates), [5, 4, 3]);
+        });
+    }
+
+    // 1 and 2 had black twice, so both must have white. Unlike the Dutch system, not even
+    // topscorers meet then in the last round.
+    #[test]
+    fn same_absolute_preferences_never_meet() {
+        let tournament = trf::replay(
+            &RATINGS[..4],
+            &[
+                &[(3, 'b', '1'), (4, 'b', '1')],
+                &[(4, 'b', '1'), (3, 'b', '1')],
+                &[(1, 'w', '0'), (2, 'w', '0')],
+                &[(2, 'w', '0'), (1, 'w', '0')],
+            ],
+            3,
+            PairingSystemType::DubovSwiss,
+        );
+
+        let available = tournament.get_players().keys().copied().collect();
+        let dutch = SwissContext::new(&tournament, &available);
+        assert!(dutch.compatible(0, 1));
+
+        with_dubov(&tournament, |dubov| {
+            assert!(!dubov.context.compatible(index(dubov, 1), index(dubov, 2)));
+        });
+    }
+}
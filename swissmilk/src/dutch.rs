@@ -1,65 +1,1599 @@
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
+use std::ops::Range;
 
 use itertools::Itertools as _;
+use smallvec::{SmallVec, smallvec};
 
-use crate::chess_types::Color;
+use crate::chess_types::{Color, ColorPreferenceLevel, Float};
+use crate::matching;
 use crate::pairing::Pairing;
 use crate::pairing_system::PairngSystem;
+use crate::player::PlayerId;
+use crate::swiss::{self, SwissContext};
 use crate::tournament::Tournament;
+use crate::trace::{BracketOutcome, BracketTrace, PairingTrace, Shortfall};
+
+// Search steps per bracket before giving up on finding the best candidate
+const MAX_BRACKET_NODES: usize = 2_000_000;
 
 pub(crate) struct DutchPairingSystem;
 
 impl PairngSystem for DutchPairingSystem {
-    fn pair_round(tournament: &mut Tournament) -> Result<Vec<Pairing>, Box<dyn Error>> {
+    #[inline]
+    fn pair_round(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, None)
+    }
+
+    #[inline]
+    fn pair_round_traced(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: &mut PairingTrace,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, Some(trace))
+    }
+}
+
+impl DutchPairingSystem {
+    fn pair(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
         if !tournament.has_started() {
-            tournament.start();
+            tournament.start()?;
         }
 
         if tournament.current_round() == 1 {
-            return Self::pair_first_round(tournament);
+            return Ok(swiss::pair_first_round(tournament, available, trace));
         }
 
-        todo!()
+        Self::pair_later_round(tournament, available, trace)
+    }
+
+    fn pair_later_round(
+        tournament: &Tournament,
+        available: &BTreeSet<PlayerId>,
+        mut trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        let context = SwissContext::new(tournament, available);
+        let everyone = (0..context.entrants.len()).collect::<Vec<_>>();
+
+        if !context.can_complete(&everyone) {
+            return Err("No pairing satisfies the absolute criteria".into());
+        }
+
+        let mut pairs = Vec::with_capacity(everyone.len() >> 1);
+        let mut bye = None;
+        let mut mdps = Vec::new();
+
+        for (i, bracket) in context.brackets.iter().enumerate() {
+            let residents = bracket.clone().collect::<Vec<_>>();
+            let is_last = i == context.brackets.len() - 1;
+
+            let mut search = BracketSearch::new(
+                &context,
+                (&mdps, &residents),
+                next_bracket(&context, i),
+                is_last,
+            )?;
+            search.run(&mdps, &residents)?;
+
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.brackets.push(search.trace(&mdps, &residents));
+            }
+
+            match search.best {
+                Some(candidate) => {
+                    pairs.extend(candidate.pairs);
+                    if is_last {
+                        bye = candidate.floaters.first().copied();
+                    } else {
+                        mdps = candidate.floaters;
+                    }
+                }
+                None if is_last => {
+                    mdps.extend(residents);
+                    let completion = context
+                        .find_completion(&mdps)
+                        .ok_or("Couldn't pair the last score bracket")?;
+
+                    if let Some(bracket) = trace.as_deref_mut().and_then(|x| x.brackets.last_mut())
+                    {
+                        let id = |x: usize| context.entrants[x].player.info.id;
+                        bracket.outcome = BracketOutcome::Completed;
+                        bracket.pairs = completion
+                            .iter()
+                            .filter_map(|&(left, right)| Some((id(left), id(right?))))
+                            .collect();
+                        bracket.floaters = completion
+                            .iter()
+                            .filter(|x| x.1.is_none())
+                            .map(|x| id(x.0))
+                            .collect();
+                    }
+
+                    for (left, right) in completion {
+                        match right {
+                            Some(right) => pairs.push((left.min(right), left.max(right))),
+                            None => bye = Some(left),
+                        }
+                    }
+                }
+                None => mdps.extend(residents),
+            }
+        }
+
+        let pairings =
+            context.to_pairings(pairs, bye, |high, low| context.allocate_colors(high, low));
+
+        if let Some(trace) = trace {
+            context.trace_boards(trace, &pairings, |high, low| {
+                context.allocate_colors_with_rule(high, low).2
+            });
+        }
+
+        Ok(pairings)
     }
 }
 
-impl DutchPairingSystem {
-    fn pair_first_round(tournament: &Tournament) -> Result<Vec<Pairing>, Box<dyn Error>> {
-        let binding = tournament
-            .get_players()
+// The bracket C7 looks at after the ith one
+#[inline]
+fn next_bracket(context: &SwissContext, i: usize) -> Option<Range<usize>> {
+    context
+        .brackets
+        .get(i + 1)
+        .filter(|_| i + 2 < context.brackets.len())
+        .cloned()
+}
+
+// The quality of each bracket of a round paired with `pairs`, from the top. Brackets are formed
+// as the engine forms them, from the residents and whoever the brackets above left unpaired.
+pub(crate) fn round_quality(
+    context: &SwissContext,
+    pairs: &[(usize, usize)],
+) -> Result<Vec<Quality>, Box<dyn Error>> {
+    let mut opponents = vec![None; context.entrants.len()];
+    for &(a, b) in pairs {
+        opponents[a] = Some(b);
+        opponents[b] = Some(a);
+    }
+
+    let mut qualities = Vec::with_capacity(context.brackets.len());
+    let mut mdps = Vec::new();
+
+    for (i, bracket) in context.brackets.iter().enumerate() {
+        let residents = bracket.clone().collect::<Vec<_>>();
+        let is_last = i == context.brackets.len() - 1;
+        let everyone = mdps.iter().chain(&residents).copied().collect::<Vec<_>>();
+
+        let (paired, floaters): (Vec<usize>, Vec<usize>) = everyone
             .iter()
-            .sorted_by_key(|(_id, player)| u16::MAX - player.pairing_number);
-        let players = binding.as_slice();
-        let mut pairings = Vec::with_capacity(tournament.player_count() >> 1);
+            .partition(|&&x| opponents[x].is_some_and(|y| everyone.contains(&y)));
+        let bracket_pairs = paired
+            .iter()
+            .filter_map(|&x| opponents[x].filter(|&y| x < y).map(|y| (x, y)))
+            .collect::<Vec<_>>();
+
+        let mut search = BracketSearch::new(
+            context,
+            (&mdps, &residents),
+            next_bracket(context, i),
+            is_last,
+        )?;
+        qualities.push(search.quality(&bracket_pairs, &floaters)?);
+        mdps = floaters;
+    }
+
+    Ok(qualities)
+}
+
+type Differences = SmallVec<[u8; 16]>;
 
-        let mut is_odd = false;
-        let mut player_count = tournament.player_count();
+// Quality criteria (C.04.3 C.5-C.19), compared lexicographically. Lower is better.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub(crate) struct Quality {
+    // C5
+    floaters: usize,
+    // C6, sorted in descending order
+    score_differences: Differences,
+    // C7, players left unpaired in the following bracket, then its score differences
+    next_bracket: (usize, Differences),
+    // C8, C9: topscorers or their opponents getting a colour difference beyond 2, or the same
+    // colour three times in a row
+    topscorer_color_differences: usize,
+    topscorer_color_repeats: usize,
+    // C10, C11
+    color_conflicts: usize,
+    strong_color_conflicts: usize,
+    // C12-C15
+    repeated_downfloats: usize,
+    repeated_upfloats: usize,
+    repeated_downfloats_two_rounds_ago: usize,
+    repeated_upfloats_two_rounds_ago: usize,
+    // C16-C19, score differences of the players counted in C12-C15, sorted in descending order
+    repeated_downfloat_differences: Differences,
+    repeated_upfloat_differences: Differences,
+    repeated_downfloat_differences_two_rounds_ago: Differences,
+    repeated_upfloat_differences_two_rounds_ago: Differences,
+}
 
-        if player_count % 2 == 1 {
-            is_odd = true;
-            player_count -= 1;
+impl Quality {
+    // Criteria where this is worse than `ideal`, score differences as their sum
+    pub(crate) fn shortfalls(&self, ideal: &Self) -> Vec<Shortfall> {
+        let sum = |x: &[u8]| x.iter().copied().map(u32::from).sum::<u32>();
+        let differences = |criterion, chosen: &Differences, ideal: &Differences| {
+            (chosen > ideal).then(|| Shortfall {
+                criterion,
+                chosen: sum(chosen),
+                ideal: sum(ideal),
+            })
+        };
+
+        [
+            Shortfall::of("C5 floaters", self.floaters, ideal.floaters),
+            differences(
+                "C6 score differences",
+                &self.score_differences,
+                &ideal.score_differences,
+            ),
+            Shortfall::of(
+                "C7 unpaired in the next bracket",
+                self.next_bracket.0,
+                ideal.next_bracket.0,
+            ),
+            (self.next_bracket.0 == ideal.next_bracket.0)
+                .then(|| {
+                    differences(
+                        "C7 next bracket score differences",
+                        &self.next_bracket.1,
+                        &ideal.next_bracket.1,
+                    )
+                })
+                .flatten(),
+            Shortfall::of(
+                "C8 topscorer colour differences",
+                self.topscorer_color_differences,
+                ideal.topscorer_color_differences,
+            ),
+            Shortfall::of(
+                "C9 topscorer colour repeats",
+                self.topscorer_color_repeats,
+                ideal.topscorer_color_repeats,
+            ),
+            Shortfall::of(
+                "C10 colour preferences",
+                self.color_conflicts,
+                ideal.color_conflicts,
+            ),
+            Shortfall::of(
+                "C11 strong colour preferences",
+                self.strong_color_conflicts,
+                ideal.strong_color_conflicts,
+            ),
+            Shortfall::of(
+                "C12 repeated downfloats",
+                self.repeated_downfloats,
+                ideal.repeated_downfloats,
+            ),
+            Shortfall::of(
+                "C13 repeated upfloats",
+                self.repeated_upfloats,
+                ideal.repeated_upfloats,
+            ),
+            Shortfall::of(
+                "C14 downfloats two rounds ago",
+                self.repeated_downfloats_two_rounds_ago,
+                ideal.repeated_downfloats_two_rounds_ago,
+            ),
+            Shortfall::of(
+                "C15 upfloats two rounds ago",
+                self.repeated_upfloats_two_rounds_ago,
+                ideal.repeated_upfloats_two_rounds_ago,
+            ),
+            differences(
+                "C16 repeated downfloat score differences",
+                &self.repeated_downfloat_differences,
+                &ideal.repeated_downfloat_differences,
+            ),
+            differences(
+                "C17 repeated upfloat score differences",
+                &self.repeated_upfloat_differences,
+                &ideal.repeated_upfloat_differences,
+            ),
+            differences(
+                "C18 score differences of downfloats two rounds ago",
+                &self.repeated_downfloat_differences_two_rounds_ago,
+                &ideal.repeated_downfloat_differences_two_rounds_ago,
+            ),
+            differences(
+                "C19 score differences of upfloats two rounds ago",
+                &self.repeated_upfloat_differences_two_rounds_ago,
+                &ideal.repeated_upfloat_differences_two_rounds_ago,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn sort(&mut self) {
+        for x in [
+            &mut self.score_differences,
+            &mut self.repeated_downfloat_differences,
+            &mut self.repeated_upfloat_differences,
+            &mut self.repeated_downfloat_differences_two_rounds_ago,
+            &mut self.repeated_upfloat_differences_two_rounds_ago,
+        ] {
+            x.sort_unstable_by(|a, b| b.cmp(a));
         }
+    }
+}
+
+struct Candidate {
+    pairs: Vec<(usize, usize)>,
+    floaters: Vec<usize>,
+    quality: Quality,
+    // S1 and S2 of the homogeneous part
+    split: (Vec<usize>, Vec<usize>),
+}
+
+// Players a partial candidate still has to pair
+#[derive(Clone, Copy)]
+enum Rest<'r> {
+    // Among themselves, this many left over
+    Any(&'r [usize], usize),
+    // Each of the first with one of the second, whoever is left of those floating
+    Split(&'r [usize], &'r [usize]),
+}
+
+// The best quality within reach is settled first, then the candidates are walked in the order of
+// C.04.3 D until the first one reaching it. Partial candidates that can't reach it are cut short.
+struct BracketSearch<'c, 'a> {
+    context: &'c SwissContext<'a>,
+    lower: usize,
+    // The following bracket, unless it's the last one (C7 only looks past the PPB)
+    next: Option<Range<usize>>,
+    is_last: bool,
+    bracket_score: u8,
+    ideal: Quality,
+
+    optimum: Option<Quality>,
+    best: Option<Candidate>,
+    examined: usize,
+    completions: HashMap<Vec<usize>, bool>,
+    outlooks: HashMap<Vec<usize>, (usize, Differences)>,
+
+    // For the trace
+    exchanges_tried: usize,
+    transpositions: usize,
+    incompatible: usize,
+    incomplete: usize,
+}
+
+impl<'c, 'a> BracketSearch<'c, 'a> {
+    fn new(
+        context: &'c SwissContext<'a>,
+        (mdps, residents): (&[usize], &[usize]),
+        next: Option<Range<usize>>,
+        is_last: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let bracket_score = residents
+            .first()
+            .map_or(0, |&resident| context.entrants[resident].score);
+
+        let mut search = Self {
+            context,
+            lower: residents.last().map_or(0, |x| x + 1),
+            next,
+            is_last,
+            bracket_score,
+            ideal: Quality::default(),
+            optimum: None,
+            best: None,
+            examined: 0,
+            completions: HashMap::new(),
+            outlooks: HashMap::new(),
+            exchanges_tried: 0,
+            transpositions: 0,
+            incompatible: 0,
+            incomplete: 0,
+        };
 
-        for i in 0..player_count >> 1 {
-            let color_left = Color::try_from((player_count % 2) as i32 + 1)?;
+        // Best conceivable outcome, no more than one player floating
+        let everyone = mdps.iter().chain(residents).copied().collect::<Vec<_>>();
+        search.ideal = search
+            .bound(&[], &[], Rest::Any(&everyone, everyone.len() % 2))?
+            .unwrap_or_default();
 
-            pairings.push(Pairing::new(
-                *players[i].0,
-                Some(*players[player_count - 1 - i].0),
-                color_left,
-                !color_left,
-            ));
+        Ok(search)
+    }
+
+    fn run(&mut self, mdps: &[usize], residents: &[usize]) -> Result<(), Box<dyn Error>> {
+        let mut everyone = mdps.iter().chain(residents).copied().collect::<Vec<_>>();
+        everyone.sort_unstable();
+
+        let most_pairs = pairable(&everyone, everyone.len() >> 1, |a, b| {
+            self.context.compatible(a, b)
+        });
+        for pair_count in (0..=most_pairs).rev() {
+            let floating = everyone.len() - (pair_count << 1);
+            if self.settle(
+                &mut Vec::new(),
+                &mut Vec::new(),
+                (&everyone, floating),
+                false,
+            )? {
+                break;
+            }
         }
 
-        if is_odd {
-            pairings.push(Pairing::new(
-                *players[player_count].0,
-                None,
-                Color::None,
-                Color::None,
-            ));
+        if self.optimum.is_none() {
+            return Ok(());
         }
 
-        Ok(pairings)
+        // Heterogeneous bracket: pair as many MDPs as possible with residents first
+        for paired_mdps in (0..=mdps.len().min(residents.len())).rev() {
+            for s1 in mdps.iter().copied().combinations(paired_mdps) {
+                let limbo = mdps.iter().filter(|x| !s1.contains(x)).copied();
+                let mut pairs = Vec::with_capacity((mdps.len() + residents.len()) >> 1);
+
+                if self.pair_mdps(&s1, residents, limbo.collect(), &mut pairs)? {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(format!(
+            "Lost the best pairing of the score {} bracket",
+            self.bracket_score
+        )
+        .into())
+    }
+
+    // Settles the best quality within reach, pairing the first of `rest` with each of the others
+    // in turn or letting it float. Returns true once it's the ideal one, or when `reaching`, once
+    // the best quality is reached.
+    fn settle(
+        &mut self,
+        pairs: &mut Vec<(usize, usize)>,
+        floaters: &mut Vec<usize>,
+        (rest, floating): (&[usize], usize),
+        reaching: bool,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.visit()?;
+
+        if floating == 0 && !self.is_complete(floaters) {
+            self.incomplete += usize::from(rest.is_empty());
+            return Ok(false);
+        }
+
+        let Some(bound) = self.bound(pairs, floaters, Rest::Any(rest, floating))? else {
+            return Ok(false);
+        };
+        let is_cut = match &self.optimum {
+            Some(optimum) if reaching => bound > *optimum,
+            Some(optimum) => bound >= *optimum,
+            None => false,
+        };
+        if is_cut {
+            return Ok(false);
+        }
+
+        // Nothing left to pair, the bound is the quality itself
+        let Some((&first, others)) = rest.split_first() else {
+            if reaching {
+                return Ok(true);
+            }
+
+            let is_ideal = bound <= self.ideal;
+            self.optimum = Some(bound);
+            return Ok(is_ideal);
+        };
+
+        if rest.len() > floating {
+            let partners = others
+                .iter()
+                .copied()
+                .filter(|&x| self.context.compatible(first, x))
+                .sorted_by_key(|&x| {
+                    (
+                        self.pair_difference(first, x),
+                        self.clashes(first, x, ColorPreferenceLevel::Mild),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            for partner in partners {
+                let rest = others
+                    .iter()
+                    .copied()
+                    .filter(|&x| x != partner)
+                    .collect::<Vec<_>>();
+
+                pairs.push((first, partner));
+                let done = self.settle(pairs, floaters, (&rest, floating), reaching)?;
+                pairs.pop();
+
+                if done {
+                    return Ok(true);
+                }
+            }
+        }
+
+        if floating > 0 {
+            floaters.push(first);
+            let done = self.settle(pairs, floaters, (others, floating - 1), reaching)?;
+            floaters.pop();
+
+            if done {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn pair_mdps(
+        &mut self,
+        s1: &[usize],
+        residents: &[usize],
+        limbo: Vec<usize>,
+        pairs: &mut Vec<(usize, usize)>,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.visit()?;
+
+        let Some((&mdp, s1)) = s1.split_first() else {
+            let remainder = limbo
+                .into_iter()
+                .chain(
+                    residents
+                        .iter()
+                        .copied()
+                        .filter(|x| !pairs.iter().any(|p| p.1 == *x)),
+                )
+                .collect::<Vec<_>>();
+
+            // Only the pairs of the best candidates are worth splitting the remainder into
+            let floating = self.optimum.as_ref().map_or(0, |x| x.floaters);
+            if remainder.len() < floating
+                || (remainder.len() - floating) % 2 == 1
+                || !self.settle(
+                    &mut pairs.clone(),
+                    &mut Vec::new(),
+                    (&remainder, floating),
+                    true,
+                )?
+            {
+                return Ok(false);
+            }
+
+            return self.pair_homogeneous(pairs, &remainder, (remainder.len() - floating) >> 1);
+        };
+
+        for &resident in residents {
+            if pairs.iter().any(|p| p.1 == resident) {
+                continue;
+            }
+
+            if !self.context.compatible(mdp, resident) {
+                self.incompatible += 1;
+                continue;
+            }
+
+            pairs.push((mdp, resident));
+            if self.pair_mdps(s1, residents, limbo.clone(), pairs)? {
+                return Ok(true);
+            }
+            pairs.pop();
+        }
+
+        Ok(false)
+    }
+
+    fn pair_homogeneous(
+        &mut self,
+        fixed: &[(usize, usize)],
+        remainder: &[usize],
+        pair_count: usize,
+    ) -> Result<bool, Box<dyn Error>> {
+        let mut is_original = true;
+
+        for_each_split(remainder, pair_count, &mut |s1, s2| {
+            self.visit()?;
+            // The first split is the original one
+            self.exchanges_tried += usize::from(!is_original);
+            is_original = false;
+
+            if !self.can_reach(fixed, s1, s2)? {
+                return Ok(false);
+            }
+
+            let mut pairs = fixed.to_vec();
+            let mut used = vec![false; s2.len()];
+            self.transpose(s1, s2, &mut used, &mut pairs)
+        })
+    }
+
+    // Walks the S2 transpositions in lexicographic order, pairing S1[i] with S2[i]
+    fn transpose(
+        &mut self,
+        s1: &[usize],
+        s2: &[usize],
+        used: &mut [bool],
+        pairs: &mut Vec<(usize, usize)>,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.visit()?;
+
+        // Players of S1 already paired are at the front
+        let depth = used.iter().filter(|x| **x).count();
+        let open = s2
+            .iter()
+            .zip(used.iter())
+            .filter(|(_, used)| !**used)
+            .map(|(x, _)| *x)
+            .collect::<Vec<_>>();
+
+        let Some(&current) = s1.get(depth) else {
+            self.transpositions += 1;
+            return self.consider(pairs, open, (s1, s2));
+        };
+
+        if depth > 0 && !self.can_reach(pairs, &s1[depth..], &open)? {
+            return Ok(false);
+        }
+
+        for i in 0..s2.len() {
+            if used[i] {
+                continue;
+            }
+
+            if !self.context.compatible(current, s2[i]) {
+                self.incompatible += 1;
+                continue;
+            }
+
+            used[i] = true;
+            pairs.push((current.min(s2[i]), current.max(s2[i])));
+
+            if self.transpose(s1, s2, used, pairs)? {
+                return Ok(true);
+            }
+
+            pairs.pop();
+            used[i] = false;
+        }
+
+        Ok(false)
+    }
+
+    // Returns true once a candidate of the best quality has been found
+    fn consider(
+        &mut self,
+        pairs: &[(usize, usize)],
+        floaters: Vec<usize>,
+        (s1, s2): (&[usize], &[usize]),
+    ) -> Result<bool, Box<dyn Error>> {
+        let quality = self.quality(pairs, &floaters)?;
+
+        if self.optimum.as_ref().is_some_and(|x| quality > *x) {
+            return Ok(false);
+        }
+
+        if !self.is_complete(&floaters) {
+            self.incomplete += 1;
+            return Ok(false);
+        }
+
+        self.best = Some(Candidate {
+            pairs: pairs.to_vec(),
+            floaters,
+            quality,
+            split: (s1.to_vec(), s2.to_vec()),
+        });
+
+        Ok(true)
+    }
+
+    // Whether pairing each of `pending` with one of `open`, on top of `pairs`, can still reach
+    // the best quality
+    fn can_reach(
+        &mut self,
+        pairs: &[(usize, usize)],
+        pending: &[usize],
+        open: &[usize],
+    ) -> Result<bool, Box<dyn Error>> {
+        if open.len() == pending.len() && !self.is_complete(&[]) {
+            return Ok(false);
+        }
+
+        let bound = self.bound(pairs, &[], Rest::Split(pending, open))?;
+        Ok(bound.is_some_and(|bound| self.optimum.as_ref().is_some_and(|x| bound <= *x)))
+    }
+
+    // Whether the lower brackets can still be paired with `floaters` moving down, or the last one
+    // with `floaters` getting the bye
+    fn is_complete(&mut self, floaters: &[usize]) -> bool {
+        if self.is_last {
+            return match floaters {
+                [] => true,
+                [bye] => self.context.can_receive_bye(*bye),
+                _ => false,
+            };
+        }
+
+        let mut rest = floaters.to_vec();
+        rest.sort_unstable();
+
+        if let Some(&known) = self.completions.get(&rest) {
+            return known;
+        }
+
+        let lower = rest
+            .iter()
+            .copied()
+            .chain(self.lower..self.context.entrants.len());
+        let known = self.context.can_complete(&lower.collect::<Vec<_>>());
+        self.completions.insert(rest, known);
+        known
+    }
+
+    #[inline]
+    fn quality(
+        &mut self,
+        pairs: &[(usize, usize)],
+        floaters: &[usize],
+    ) -> Result<Quality, Box<dyn Error>> {
+        self.bound(pairs, floaters, Rest::Any(&[], 0))
+            .map(Option::unwrap_or_default)
+    }
+
+    // Most pairs among `players` granting every colour preference of at least `level`. A
+    // granting pair holds at most one player preferring either colour, so the commoner colour
+    // bounds the pairs. Its players go first, taking the other colour before anyone else, which
+    // lets the greedy pass reach the bound.
+    fn granting_pairs(
+        &self,
+        players: &[usize],
+        pair_count: usize,
+        level: ColorPreferenceLevel,
+    ) -> usize {
+        let color = |x: usize| {
+            let preference = self.context.entrants[x].preference;
+            if preference.level() >= level {
+                preference.color()
+            } else {
+                Color::None
+            }
+        };
+        let whites = players.iter().filter(|&&x| color(x) == Color::White);
+        let blacks = players.iter().filter(|&&x| color(x) == Color::Black);
+        let (whites, blacks) = (whites.count(), blacks.count());
+
+        let commoner = if whites >= blacks {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let players = players
+            .iter()
+            .copied()
+            .sorted_by_key(|&x| {
+                let color = color(x);
+                (color != commoner, color != !commoner)
+            })
+            .collect::<Vec<_>>();
+
+        let most = players.len() - whites.max(blacks);
+        pairable(&players, pair_count.min(most), |a, b| {
+            self.context.compatible(a, b) && !self.clashes(a, b, level)
+        })
+    }
+
+    // The best quality of any candidate completing `pairs` and `floaters` with `rest`, every
+    // criterion bounded from below on its own. None if the rest can't be paired.
+    fn bound(
+        &mut self,
+        pairs: &[(usize, usize)],
+        floaters: &[usize],
+        rest: Rest,
+    ) -> Result<Option<Quality>, Box<dyn Error>> {
+        let (players, floating, pair_count) = match rest {
+            Rest::Any(players, floating) => (players, floating, (players.len() - floating) >> 1),
+            Rest::Split(pending, open) => (open, open.len() - pending.len(), pending.len()),
+        };
+
+        // Most pairs that can be made granting every colour preference, or the strong ones
+        let most_pairs = |level: Option<ColorPreferenceLevel>| {
+            let fits = |a, b| {
+                self.context.compatible(a, b)
+                    && level.is_none_or(|level| !self.clashes(a, b, level))
+            };
+
+            match (rest, level) {
+                (Rest::Any(players, _), None) => pairable(players, pair_count, fits),
+                (Rest::Any(players, _), Some(level)) => {
+                    self.granting_pairs(players, pair_count, level)
+                }
+                (Rest::Split(pending, open), _) => assignable(pending, open, fits),
+            }
+        };
+
+        let granted = most_pairs(Some(ColorPreferenceLevel::Mild));
+        let strongly_granted = if granted < pair_count {
+            most_pairs(Some(ColorPreferenceLevel::Strong))
+        } else {
+            granted
+        };
+        if strongly_granted < pair_count && most_pairs(None) < pair_count {
+            return Ok(None);
+        }
+
+        let mut bound = self.pairs_quality(pairs);
+        bound.floaters = floaters.len() + floating;
+        bound.color_conflicts += pair_count - granted;
+        bound.strong_color_conflicts += pair_count - strongly_granted;
+
+        // Each player meets one at least as close in score as the closest one
+        let closest = |x: usize, others: &[usize]| {
+            let mut closest = None;
+            for &y in others {
+                if y == x || !self.context.compatible(x, y) {
+                    continue;
+                }
+
+                let difference = self.pair_difference(x, y);
+                if difference == 0 {
+                    return Some(0);
+                }
+                closest = Some(closest.map_or(difference, |x: u8| x.min(difference)));
+            }
+            closest
+        };
+        let pending_differences = match rest {
+            // Of the players closest to one another from the farthest, the (2i - 1 + floating)th
+            // is in the ith pair from the farthest or an even farther one
+            Rest::Any(players, floating) => players
+                .iter()
+                .map(|&x| closest(x, players).unwrap_or_default())
+                .sorted_unstable_by(|a, b| b.cmp(a))
+                .skip(floating)
+                .step_by(2)
+                .take(pair_count)
+                .collect::<Vec<_>>(),
+            Rest::Split(pending, open) => {
+                pending.iter().filter_map(|&x| closest(x, open)).collect()
+            }
+        };
+        let floater_differences = players
+            .iter()
+            .map(|&x| self.floater_difference(x))
+            .sorted_unstable()
+            .take(floating);
+
+        bound.score_differences = pairs
+            .iter()
+            .map(|&(a, b)| self.pair_difference(a, b))
+            .chain(floaters.iter().map(|&x| self.floater_difference(x)))
+            .chain(pending_differences)
+            .chain(floater_differences)
+            .collect();
+
+        bound.next_bracket = self.outlook_bound(floaters, players, floating)?;
+
+        for &floater in floaters {
+            let player = self.context.entrants[floater].player;
+            let difference = self.floater_difference(floater);
+
+            if player.last_float(1) == Float::Down {
+                bound.repeated_downfloats += 1;
+                bound.repeated_downfloat_differences.push(difference);
+            }
+            if player.last_float(2) == Float::Down {
+                bound.repeated_downfloats_two_rounds_ago += 1;
+                bound
+                    .repeated_downfloat_differences_two_rounds_ago
+                    .push(difference);
+            }
+        }
+
+        // Only so many of the rest can float without having floated down before
+        let fresh = |rounds_ago: usize| {
+            players
+                .iter()
+                .filter(|&&x| self.context.entrants[x].player.last_float(rounds_ago) != Float::Down)
+                .count()
+        };
+        bound.repeated_downfloats += floating.saturating_sub(fresh(1));
+        bound.repeated_downfloats_two_rounds_ago += floating.saturating_sub(fresh(2));
+
+        bound.sort();
+        Ok(Some(bound))
+    }
+
+    // The criteria that only depend on the pairs made
+    fn pairs_quality(&self, pairs: &[(usize, usize)]) -> Quality {
+        let entrants = &self.context.entrants;
+        let mut quality = Quality::default();
+
+        for &(high, low) in pairs {
+            let (a, b) = (&entrants[high], &entrants[low]);
+            let (a_pref, b_pref) = (a.preference, b.preference);
+
+            if a_pref.color() != Color::None && a_pref.color() == b_pref.color() {
+                quality.color_conflicts += 1;
+
+                if a_pref.level() >= ColorPreferenceLevel::Strong
+                    && b_pref.level() >= ColorPreferenceLevel::Strong
+                {
+                    quality.strong_color_conflicts += 1;
+                }
+
+                // Only topscorers meet with the same absolute preference (C3), one loses it
+                if a_pref.level() == ColorPreferenceLevel::Absolute
+                    && b_pref.level() == ColorPreferenceLevel::Absolute
+                {
+                    let (high_color, low_color) = self.context.allocate_colors(high, low);
+                    let (loser, color) = if high_color == a_pref.color() {
+                        (b.player, low_color)
+                    } else {
+                        (a.player, high_color)
+                    };
+
+                    let step = if color == Color::White { 1 } else { -1 };
+                    if (loser.color_difference + step).abs() > 2 {
+                        quality.topscorer_color_differences += 1;
+                    }
+                    if loser
+                        .color_history
+                        .iter()
+                        .rev()
+                        .take(2)
+                        .filter(|&&x| x == color)
+                        .count()
+                        == 2
+                    {
+                        quality.topscorer_color_repeats += 1;
+                    }
+                }
+            }
+
+            if a.score != b.score {
+                let upfloater = if a.score < b.score { a } else { b };
+                let difference = a.score.abs_diff(b.score);
+
+                if upfloater.player.last_float(1) == Float::Up {
+                    quality.repeated_upfloats += 1;
+                    quality.repeated_upfloat_differences.push(difference);
+                }
+                if upfloater.player.last_float(2) == Float::Up {
+                    quality.repeated_upfloats_two_rounds_ago += 1;
+                    quality
+                        .repeated_upfloat_differences_two_rounds_ago
+                        .push(difference);
+                }
+            }
+        }
+
+        quality
+    }
+
+    // Whether `a` and `b` both prefer the same colour, at least at `level`
+    #[inline]
+    fn clashes(&self, a: usize, b: usize, level: ColorPreferenceLevel) -> bool {
+        let (a, b) = (
+            self.context.entrants[a].preference,
+            self.context.entrants[b].preference,
+        );
+
+        a.color() != Color::None
+            && a.color() == b.color()
+            && a.level() >= level
+            && b.level() >= level
+    }
+
+    // C7 at best, were `floating` of `players` to join `floaters`
+    fn outlook_bound(
+        &mut self,
+        floaters: &[usize],
+        players: &[usize],
+        floating: usize,
+    ) -> Result<(usize, Differences), Box<dyn Error>> {
+        Ok(match floating {
+            0 => self.outlook(floaters)?,
+            // A lone floater pairs with a resident of the following bracket, at least as far in
+            // score as the bracket is below it, or is left unpaired even farther. Floaters are
+            // tried from the closest until none can beat the best outlook.
+            1 if floaters.is_empty() => match self.next.clone() {
+                Some(next) => {
+                    let entrants = &self.context.entrants;
+                    let next_score = entrants[next.start].score;
+                    let unpaired = (next.len() + 1) % 2;
+                    let length = ((next.len() + 1 - unpaired) >> 1) + unpaired;
+                    let lowest = |x: usize| {
+                        let mut differences = smallvec![0; length];
+                        differences[0] = entrants[x].score.saturating_sub(next_score);
+                        (unpaired, differences)
+                    };
+
+                    let mut best: Option<(usize, Differences)> = None;
+                    for x in players
+                        .iter()
+                        .copied()
+                        .sorted_by_key(|&x| entrants[x].score)
+                    {
+                        if best.as_ref().is_some_and(|best| *best <= lowest(x)) {
+                            break;
+                        }
+
+                        let outlook = self.outlook(&[x])?;
+                        if best.as_ref().is_none_or(|best| outlook < *best) {
+                            best = Some(outlook);
+                        }
+                    }
+                    best.unwrap_or_default()
+                }
+                None => Default::default(),
+            },
+            1 if self.next.is_some() => {
+                let mut key = floaters.to_vec();
+                key.sort_unstable();
+
+                for &x in players {
+                    let at = key.partition_point(|&y| y < x);
+                    key.insert(at, x);
+                    self.cache_outlook(&key)?;
+                    key.remove(at);
+                }
+
+                let mut best = None;
+                for &x in players {
+                    let at = key.partition_point(|&y| y < x);
+                    key.insert(at, x);
+                    let outlook = &self.outlooks[key.as_slice()];
+                    if best.is_none_or(|best| outlook < best) {
+                        best = Some(outlook);
+                    }
+                    key.remove(at);
+                }
+                best.cloned().unwrap_or_default()
+            }
+            1 => Default::default(),
+            // As many elements as there are with the fewest players left unpaired
+            _ => match &self.next {
+                Some(next) => {
+                    let players = next.len() + floaters.len() + floating;
+                    let unpaired = players % 2;
+                    (unpaired, smallvec![0; (players + unpaired) >> 1])
+                }
+                None => Default::default(),
+            },
+        })
+    }
+
+    // C7: players left unpaired in the following bracket and its score differences, were
+    // `floaters` to move down into it
+    fn outlook(&mut self, floaters: &[usize]) -> Result<(usize, Differences), Box<dyn Error>> {
+        if self.next.is_none() {
+            return Ok(Default::default());
+        }
+
+        let mut key = floaters.to_vec();
+        key.sort_unstable();
+        self.cache_outlook(&key)?;
+        Ok(self.outlooks[&key].clone())
+    }
+
+    // Works out the outlook of the sorted `floaters` unless it's known already
+    fn cache_outlook(&mut self, key: &[usize]) -> Result<(), Box<dyn Error>> {
+        let Some(next) = self.next.clone() else {
+            return Ok(());
+        };
+
+        if self.outlooks.contains_key(key) {
+            return Ok(());
+        }
+
+        let players = key.iter().copied().chain(next.clone()).collect::<Vec<_>>();
+        let entrants = &self.context.entrants;
+        let next_score = entrants[next.start].score;
+        let unpaired =
+            |x: usize| (entrants[x].score + self.context.win_score).saturating_sub(next_score);
+
+        // The score differences are weighed so that a heavier matching, among the ones with the
+        // most pairs, always has the smaller differences from the largest down: each difference
+        // outweighs any number of smaller ones that can occur together
+        let mut edges = Vec::new();
+        for (i, &a) in players.iter().enumerate() {
+            for (j, &b) in players.iter().enumerate().skip(i + 1) {
+                if self.context.compatible(a, b) {
+                    edges.push((i, j, self.pair_difference(a, b)));
+                }
+            }
+        }
+
+        let values = edges
+            .iter()
+            .map(|x| x.2)
+            .chain(players.iter().map(|&x| unpaired(x)))
+            .sorted_unstable()
+            .dedup()
+            .collect::<Vec<_>>();
+
+        // Only a pair or floater of the bracket's own residents can repeat a difference
+        // more often than there are MDPs
+        let mut weight = 1 as matching::Weight;
+        let mut weights = HashMap::with_capacity(values.len());
+        for &value in &values {
+            weights.insert(value, weight);
+            let repeats = if value == 0 || value == self.context.win_score {
+                players.len()
+            } else {
+                key.len()
+            };
+            weight = matching::Weight::try_from(repeats + 1)
+                .ok()
+                .and_then(|x| weight.checked_mul(x))
+                .ok_or("Too many score differences to weigh the following bracket")?;
+        }
+        weight
+            .checked_mul(2)
+            .ok_or("Too many score differences to weigh the following bracket")?;
+
+        let edges = edges
+            .into_iter()
+            .map(|(i, j, difference)| {
+                let saved = weights[&unpaired(players[i])] + weights[&unpaired(players[j])]
+                    - weights[&difference];
+                (i, j, saved)
+            })
+            .collect::<Vec<_>>();
+
+        let mates = matching::maximum_weight_matching(players.len(), &edges, true);
+        let mut differences = Differences::new();
+        let mut unpaired_count = 0;
+        for (i, mate) in mates.iter().enumerate() {
+            match *mate {
+                Some(j) if i < j => differences.push(self.pair_difference(players[i], players[j])),
+                Some(_) => {}
+                None => {
+                    unpaired_count += 1;
+                    differences.push(unpaired(players[i]));
+                }
+            }
+        }
+        differences.sort_unstable_by(|a, b| b.cmp(a));
+
+        self.outlooks
+            .insert(key.to_vec(), (unpaired_count, differences));
+        Ok(())
+    }
+
+    #[inline]
+    fn pair_difference(&self, a: usize, b: usize) -> u8 {
+        let entrants = &self.context.entrants;
+        entrants[a].score.abs_diff(entrants[b].score)
+    }
+
+    // A downfloater is measured against an artificial opponent a win below the bracket
+    #[inline]
+    fn floater_difference(&self, x: usize) -> u8 {
+        (self.context.entrants[x].score + self.context.win_score).saturating_sub(self.bracket_score)
+    }
+
+    fn visit(&mut self) -> Result<(), Box<dyn Error>> {
+        self.examined += 1;
+        if self.examined > MAX_BRACKET_NODES {
+            return Err(format!(
+                "Gave up on the score {} bracket after {MAX_BRACKET_NODES} search steps",
+                self.bracket_score
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    fn trace(&self, mdps: &[usize], residents: &[usize]) -> BracketTrace {
+        let id = |x: usize| self.context.entrants[x].player.info.id;
+        let ids = |x: &[usize]| x.iter().map(|&x| id(x)).collect();
+
+        let mut trace = BracketTrace {
+            score: self.bracket_score,
+            residents: ids(residents),
+            downfloaters: ids(mdps),
+            exchanges_tried: self.exchanges_tried,
+            transpositions_tried: self.transpositions,
+            incompatible: self.incompatible,
+            incomplete: self.incomplete,
+            outcome: BracketOutcome::MovedDown,
+            ..Default::default()
+        };
+
+        if let Some(best) = &self.best {
+            trace.outcome = BracketOutcome::Paired;
+            trace.s1 = ids(&best.split.0);
+            trace.s2 = ids(&best.split.1);
+            trace.pairs = best.pairs.iter().map(|&(a, b)| (id(a), id(b))).collect();
+            trace.floaters = ids(&best.floaters);
+            trace.shortfalls = best.quality.shortfalls(&self.ideal);
+        }
+
+        trace
+    }
+}
+
+// Visits the S1/S2 splits of a homogeneous bracket with `pair_count` pairs until `visit` returns
+// true: the original split first, then the resident exchanges ordered by their size, by the
+// difference of the exchanged BSNs, from the highest BSN leaving S1 and the lowest one leaving S2
+fn for_each_split(
+    remainder: &[usize],
+    pair_count: usize,
+    visit: &mut impl FnMut(&[usize], &[usize]) -> Result<bool, Box<dyn Error>>,
+) -> Result<bool, Box<dyn Error>> {
+    let (s1, s2) = remainder.split_at(pair_count);
+    if visit(s1, s2)? {
+        return Ok(true);
+    }
+
+    for size in 1..=s1.len().min(s2.len()) {
+        // Positions in S1 and S2, a position in S2 being s1.len() further down in BSN
+        let lowest = size * (size - 1) / 2;
+        let highest = |n: usize| size * (2 * n - size - 1) / 2;
+        let offset = s1.len() * size;
+
+        for difference in
+            (lowest + offset - highest(s1.len()))..=(highest(s2.len()) + offset - lowest)
+        {
+            let mut out = Vec::with_capacity(size);
+            let done = combinations_descending(s1.len(), size, &mut out, &mut |out| {
+                let target = (difference + out.iter().sum::<usize>()).checked_sub(offset);
+                let Some(target) = target else {
+                    return Ok(false);
+                };
+
+                let mut into = Vec::with_capacity(size);
+                combinations_with_sum(s2.len(), size, target, 0, &mut into, &mut |into| {
+                    let mut new_s1 = s1.to_vec();
+                    let mut new_s2 = s2.to_vec();
+
+                    for (&i, &j) in out.iter().zip(into) {
+                        std::mem::swap(&mut new_s1[i], &mut new_s2[j]);
+                    }
+
+                    new_s1.sort_unstable();
+                    new_s2.sort_unstable();
+                    visit(&new_s1, &new_s2)
+                })
+            })?;
+
+            if done {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+// `size` positions out of 0..n, highest first, the sets with the higher highest position first
+fn combinations_descending(
+    n: usize,
+    size: usize,
+    combination: &mut Vec<usize>,
+    visit: &mut impl FnMut(&[usize]) -> Result<bool, Box<dyn Error>>,
+) -> Result<bool, Box<dyn Error>> {
+    let left = size - combination.len();
+    if left == 0 {
+        return visit(combination);
+    }
+
+    let end = combination.last().copied().unwrap_or(n);
+    for first in (left - 1..end).rev() {
+        combination.push(first);
+        let done = combinations_descending(n, size, combination, visit)?;
+        combination.pop();
+
+        if done {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// `size` positions from `start` to n adding up to `sum`, in ascending lexicographic order
+fn combinations_with_sum(
+    n: usize,
+    size: usize,
+    sum: usize,
+    start: usize,
+    combination: &mut Vec<usize>,
+    visit: &mut impl FnMut(&[usize]) -> Result<bool, Box<dyn Error>>,
+) -> Result<bool, Box<dyn Error>> {
+    let left = size - combination.len();
+    if left == 0 {
+        return if sum == 0 {
+            visit(combination)
+        } else {
+            Ok(false)
+        };
+    }
+
+    for first in start..=n - left {
+        // The smallest and the largest sums the rest can still make
+        let lowest = first * left + left * (left - 1) / 2;
+        let highest = first + (left - 1) * (2 * n - left) / 2;
+        if lowest > sum {
+            break;
+        }
+        if highest < sum {
+            continue;
+        }
+
+        combination.push(first);
+        let done = combinations_with_sum(n, size, sum - first, first + 1, combination, visit)?;
+        combination.pop();
+
+        if done {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// Most pairs among `players` that `fits` allows, counting up to `needed`
+fn pairable(players: &[usize], needed: usize, fits: impl Fn(usize, usize) -> bool) -> usize {
+    // Often enough a greedy pass finds them all
+    let mut paired = vec![false; players.len()];
+    let mut greedy = 0;
+    for i in 0..players.len() {
+        if paired[i] {
+            continue;
+        }
+
+        if let Some(j) =
+            (i + 1..players.len()).find(|&j| !paired[j] && fits(players[i], players[j]))
+        {
+            paired[i] = true;
+            paired[j] = true;
+            greedy += 1;
+        }
+    }
+    if greedy >= needed {
+        return needed;
+    }
+
+    let edges = players
+        .iter()
+        .enumerate()
+        .tuple_combinations()
+        .filter(|&((_, &a), (_, &b))| fits(a, b))
+        .map(|((i, _), (j, _))| (i, j, 1))
+        .collect::<Vec<_>>();
+    let pairs = matching::maximum_weight_matching(players.len(), &edges, true)
+        .iter()
+        .flatten()
+        .count()
+        >> 1;
+
+    pairs.min(needed)
+}
+
+// Most of `pending` that can meet a different one of `open` each, as far as `fits` allows
+fn assignable(pending: &[usize], open: &[usize], fits: impl Fn(usize, usize) -> bool) -> usize {
+    fn augment(
+        x: usize,
+        open: &[usize],
+        fits: &impl Fn(usize, usize) -> bool,
+        mates: &mut [Option<usize>],
+        seen: &mut [bool],
+    ) -> bool {
+        for (j, &y) in open.iter().enumerate() {
+            if seen[j] || !fits(x, y) {
+                continue;
+            }
+
+            seen[j] = true;
+            if mates[j].is_none_or(|mate| augment(mate, open, fits, mates, seen)) {
+                mates[j] = Some(x);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    let mut mates = vec![None; open.len()];
+    pending
+        .iter()
+        .filter(|&&x| {
+            let mut seen = vec![false; open.len()];
+            augment(x, open, &fits, &mut mates, &mut seen)
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Differences, Quality, for_each_split};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::PlayerId;
+    use crate::tournament::Tournament;
+    use crate::trf;
+
+    const RATINGS: [u16; 10] = [2500, 2450, 2400, 2350, 2300, 2250, 2200, 2150, 2100, 2050];
+
+    // S1 of the first `count` splits of a homogeneous bracket, in BSNs
+    fn splits(players: usize, count: usize) -> Vec<Vec<usize>> {
+        let remainder = (0..players).collect::<Vec<_>>();
+        let mut splits = Vec::new();
+
+        for_each_split(&remainder, players >> 1, &mut |s1, _| {
+            splits.push(s1.iter().map(|x| x + 1).collect());
+            Ok(splits.len() == count)
+        })
+        .unwrap();
+
+        splits
+    }
+
+    fn pairs(
+        tournament: &mut Tournament,
+    ) -> (Vec<PlayerId>, Vec<PlayerId>, Vec<(PlayerId, PlayerId)>) {
+        let (_, trace) = tournament.pair_with_trace().unwrap();
+        let bracket = trace.brackets.into_iter().next().unwrap();
+        let mut pairs = bracket
+            .pairs
+            .iter()
+            .map(|&(a, b)| (a.min(b), a.max(b)))
+            .collect::<Vec<_>>();
+        pairs.sort_unstable();
+
+        (bracket.s1, bracket.s2, pairs)
+    }
+
+    // C.04.3 D.2: fewest players exchanged, then the smallest difference of the BSN sums, then
+    // the highest BSNs leaving S1 and the lowest ones leaving S2
+    #[test]
+    fn exchanges_follow_the_handbook_order() {
+        let single = splits(10, 16);
+        let exchanged = single
+            .iter()
+            .skip(1)
+            .map(|s1| {
+                let out = (1..=5).find(|x| !s1.contains(x)).unwrap();
+                let into = s1.iter().copied().find(|&x| x > 5).unwrap();
+                (out, into)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(single[0], [1, 2, 3, 4, 5]);
+        assert_eq!(
+            exchanged,
+            [
+                (5, 6),
+                (5, 7),
+                (4, 6),
+                (5, 8),
+                (4, 7),
+                (3, 6),
+                (5, 9),
+                (4, 8),
+                (3, 7),
+                (2, 6),
+                (5, 10),
+                (4, 9),
+                (3, 8),
+                (2, 7),
+                (1, 6),
+            ]
+        );
+
+        // Of 25 single exchanges, then the two-player ones
+        let double = splits(10, 29);
+        assert_eq!(
+            double[26..],
+            [
+                vec![1, 2, 3, 6, 7],
+                vec![1, 2, 3, 6, 8],
+                vec![1, 2, 4, 6, 7]
+            ]
+        );
+    }
+
+    // Everyone drew round 1, so every S1 player has met its natural S2 opponent. The first S2
+    // transposition granting every colour preference is 8 9 10 7 6.
+    #[test]
+    fn transpositions_come_before_exchanges() {
+        let mut tournament = trf::replay(
+            &RATINGS,
+            &[
+                &[(6, 'w', '=')],
+                &[(7, 'b', '=')],
+                &[(8, 'w', '=')],
+                &[(9, 'b', '=')],
+                &[(10, 'w', '=')],
+                &[(1, 'b', '=')],
+                &[(2, 'w', '=')],
+                &[(3, 'b', '=')],
+                &[(4, 'w', '=')],
+                &[(5, 'b', '=')],
+            ],
+            5,
+            PairingSystemType::ClassicSwiss,
+        );
+
+        let (s1, s2, pairs) = pairs(&mut tournament);
+        assert_eq!(s1, [1, 2, 3, 4, 5]);
+        assert_eq!(s2, [6, 7, 8, 9, 10]);
+        assert_eq!(pairs, [(1, 8), (2, 9), (3, 10), (4, 7), (5, 6)]);
+    }
+
+    // 1 has met 3 and 4, so no transposition pairs the bracket. Exchanging 2 and 3 does.
+    #[test]
+    fn exchange_when_no_transposition_pairs() {
+        let mut tournament = trf::replay(
+            &RATINGS[..4],
+            &[
+                &[(3, 'w', '='), (4, 'b', '=')],
+                &[(4, 'b', '='), (3, 'w', '=')],
+                &[(1, 'b', '='), (2, 'b', '=')],
+                &[(2, 'w', '='), (1, 'w', '=')],
+            ],
+            5,
+            PairingSystemType::ClassicSwiss,
+        );
+
+        let (s1, s2, pairs) = pairs(&mut tournament);
+        assert_eq!(s1, [1, 3]);
+        assert_eq!(s2, [2, 4]);
+        assert_eq!(pairs, [(1, 2), (3, 4)]);
+    }
+
+    // Falling short on a criterion outweighs any gain on the ones after it
+    #[test]
+    fn quality_criteria_come_in_handbook_order() {
+        let differences = |x: &[u8]| x.iter().copied().collect::<Differences>();
+        let criteria: [fn(&mut Quality, usize); 15] = [
+            |q, x| q.floaters = x,
+            |q, x| q.score_differences = std::iter::repeat_n(1, x).collect(),
+            |q, x| q.next_bracket.0 = x,
+            |q, x| q.topscorer_color_differences = x,
+            |q, x| q.topscorer_color_repeats = x,
+            |q, x| q.color_conflicts = x,
+            |q, x| q.strong_color_conflicts = x,
+            |q, x| q.repeated_downfloats = x,
+            |q, x| q.repeated_upfloats = x,
+            |q, x| q.repeated_downfloats_two_rounds_ago = x,
+            |q, x| q.repeated_upfloats_two_rounds_ago = x,
+            |q, x| q.repeated_downfloat_differences = std::iter::repeat_n(1, x).collect(),
+            |q, x| q.repeated_upfloat_differences = std::iter::repeat_n(1, x).collect(),
+            |q, x| {
+                q.repeated_downfloat_differences_two_rounds_ago =
+                    std::iter::repeat_n(1, x).collect();
+            },
+            |q, x| {
+                q.repeated_upfloat_differences_two_rounds_ago = std::iter::repeat_n(1, x).collect();
+            },
+        ];
+
+        for (i, pair) in criteria.windows(2).enumerate() {
+            let (mut earlier, mut later) = (Quality::default(), Quality::default());
+            pair[0](&mut earlier, 1);
+            pair[1](&mut later, 10);
+            assert!(earlier > later, "Criterion {} of C5-C19", i + 5);
+        }
+
+        // C6 goes from the largest difference down, and a larger one outweighs any smaller ones
+        let (mut one_large, mut many_small) = (Quality::default(), Quality::default());
+        one_large.score_differences = differences(&[2, 0]);
+        many_small.score_differences = differences(&[1, 1, 1]);
+        assert!(one_large > many_small);
     }
 }
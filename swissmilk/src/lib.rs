@@ -11,9 +11,24 @@
 pub mod random;
 pub mod chess_types;
 pub mod pairing;
+pub mod round;
+pub mod rtg;
+pub mod standings;
+pub mod tiebreak;
+pub mod trf;
 pub mod player;
 pub mod tournament;
 pub mod dutch;
+pub mod dubov;
+pub mod burstein;
+pub mod lim;
 pub mod pairing_system;
 pub mod berger_table;
+pub mod varma;
+pub mod scheveningen;
+pub mod acceleration;
+pub mod checker;
+pub mod trace;
+pub mod matching;
+mod swiss;
 mod utils;
\ No newline at end of file
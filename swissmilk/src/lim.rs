@@ -0,0 +1,676 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use itertools::Itertools as _;
+use smallvec::SmallVec;
+
+use crate::chess_types::{Color, Float};
+use crate::pairing::Pairing;
+use crate::pairing_system::PairngSystem;
+use crate::player::{Player, PlayerId};
+use crate::swiss::{self, Entrant, SwissContext};
+use crate::tournament::Tournament;
+use crate::trace::{BracketOutcome, BracketTrace, ColorRule, PairingTrace, Shortfall, Tries};
+
+// Search steps per score group before giving up, floater sets included
+const MAX_GROUP_NODES: usize = 2_000_000;
+
+pub(crate) struct LimPairingSystem;
+
+// Pairs made inside a score group, and the players floating out of it
+type GroupPairing = (Vec<(usize, usize)>, Vec<usize>);
+
+// The colour a player is due, and whether anything else would give them the same colour three
+// times in a row or three more of one colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Due {
+    color: Color,
+    is_absolute: bool,
+    imbalance: u8,
+}
+
+impl Due {
+    // The colour evening out whites and blacks, alternating from the last game when even
+    fn of(player: &Player) -> Self {
+        let imbalance = player.color_difference.unsigned_abs();
+        let repeated = match player.color_history.as_slice() {
+            [.., a, b] if a == b => Some(!*b),
+            _ => None,
+        };
+
+        let color = repeated.unwrap_or(match player.color_difference.signum() {
+            1 => Color::Black,
+            -1 => Color::White,
+            _ => player.color_history.last().map_or(Color::None, |&x| !x),
+        });
+
+        Self {
+            color,
+            is_absolute: repeated.is_some() || imbalance > 1,
+            imbalance,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Down,
+    Up,
+}
+
+impl Direction {
+    #[inline]
+    const fn float(self) -> Float {
+        match self {
+            Self::Down => Float::Down,
+            Self::Up => Float::Up,
+        }
+    }
+
+    // Where the trace lists the players floating into a group paired in this direction
+    const fn floated_in(self, trace: &mut BracketTrace) -> &mut Vec<PlayerId> {
+        match self {
+            Self::Down => &mut trace.downfloaters,
+            Self::Up => &mut trace.upfloaters,
+        }
+    }
+}
+
+impl PairngSystem for LimPairingSystem {
+    #[inline]
+    fn pair_round(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, None)
+    }
+
+    #[inline]
+    fn pair_round_traced(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: &mut PairingTrace,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        Self::pair(tournament, available, Some(trace))
+    }
+}
+
+impl LimPairingSystem {
+    fn pair(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        if !tournament.has_started() {
+            tournament.start()?;
+        }
+
+        if tournament.current_round() == 1 {
+            return Ok(swiss::pair_first_round(tournament, available, trace));
+        }
+
+        Self::pair_later_round(tournament, available, trace)
+    }
+
+    fn pair_later_round(
+        tournament: &Tournament,
+        available: &BTreeSet<PlayerId>,
+        mut trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        let context =
+            SwissContext::new_with_color_compatibility(tournament, available, colors_compatible);
+        let everyone = (0..context.entrants.len()).collect::<Vec<_>>();
+
+        if !context.can_complete(&everyone) {
+            return Err("No pairing satisfies the absolute criteria".into());
+        }
+
+        // The median group holds the players on exactly half of the possible points. It goes
+        // by the real score, so accelerated players are paired on the side of what they scored.
+        let rounds_played = u16::from(tournament.current_round().saturating_sub(1));
+        let median = u16::from(context.win_score) * rounds_played;
+        let side = |x: &usize| (2 * u16::from(context.entrants[*x].player.score)).cmp(&median);
+
+        let groups = |half: Ordering| {
+            context
+                .brackets
+                .iter()
+                .map(|bracket| {
+                    bracket
+                        .clone()
+                        .filter(|x| side(x) == half)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+        };
+        let upper = groups(Ordering::Greater);
+        let lower = groups(Ordering::Less).into_iter().rev().collect::<Vec<_>>();
+
+        let mut unpaired = vec![true; everyone.len()];
+        let mut pairs = Vec::with_capacity(everyone.len() >> 1);
+        let mut median_group = everyone
+            .iter()
+            .copied()
+            .filter(|x| side(x) == Ordering::Equal)
+            .collect::<Vec<_>>();
+        let mut median_trace = BracketTrace {
+            score: u8::try_from(median >> 1)?,
+            residents: context.ids(&median_group),
+            ..Default::default()
+        };
+
+        // Upper groups are paired top-down and float down, lower groups bottom-up and float up
+        for (groups, direction) in [(upper, Direction::Down), (lower, Direction::Up)] {
+            let mut floaters = Vec::new();
+
+            for residents in groups {
+                let incoming = std::mem::take(&mut floaters);
+                let mut group = incoming
+                    .iter()
+                    .chain(&residents)
+                    .copied()
+                    .collect::<Vec<_>>();
+                group.sort_unstable();
+
+                for &x in &group {
+                    unpaired[x] = false;
+                }
+
+                let rest = (0..everyone.len())
+                    .filter(|&x| unpaired[x])
+                    .collect::<Vec<_>>();
+                let mut tries = Tries::default();
+                let (group_pairs, leftovers) =
+                    Self::pair_group(&context, &group, &rest, direction, &mut tries)?
+                        .unwrap_or_else(|| (Vec::new(), group.clone()));
+                floaters = leftovers;
+
+                for &x in &floaters {
+                    unpaired[x] = true;
+                }
+
+                if let Some(trace) = trace.as_deref_mut() {
+                    let mut bracket_trace =
+                        Self::bracket_trace(&context, &residents, &group_pairs, &floaters);
+                    *direction.floated_in(&mut bracket_trace) = context.ids(&incoming);
+                    bracket_trace.shortfalls = shortfalls(&context, &group, &floaters, direction);
+                    bracket_trace.set_tries(tries);
+                    trace.brackets.push(bracket_trace);
+                }
+
+                pairs.extend(group_pairs);
+            }
+
+            *direction.floated_in(&mut median_trace) = context.ids(&floaters);
+            median_group.extend(floaters);
+        }
+
+        median_group.sort_unstable();
+
+        let mut tries = Tries::default();
+        let ((median_pairs, leftovers), outcome) =
+            Self::pair_median_group(&context, &median_group, &mut tries)?;
+
+        if let Some(trace) = trace.as_deref_mut() {
+            let id = |x: usize| context.entrants[x].player.info.id;
+            median_trace.outcome = outcome;
+            median_trace.pairs = median_pairs.iter().map(|&(a, b)| (id(a), id(b))).collect();
+            median_trace.floaters = context.ids(&leftovers);
+            median_trace.shortfalls =
+                shortfalls(&context, &median_group, &leftovers, Direction::Down);
+            median_trace.set_tries(tries);
+            trace.brackets.push(median_trace);
+        }
+
+        pairs.extend(median_pairs);
+
+        let pairings = context.to_pairings(pairs, leftovers.first().copied(), |high, low| {
+            let (high_color, low_color, _) = allocate_colors_with_rule(&context, high, low);
+            (high_color, low_color)
+        });
+
+        if let Some(trace) = trace {
+            context.trace_boards(trace, &pairings, |high, low| {
+                allocate_colors_with_rule(&context, high, low).2
+            });
+        }
+
+        Ok(pairings)
+    }
+
+    // The median group comes last and takes everyone left, so it falls back on any completion
+    fn pair_median_group(
+        context: &SwissContext,
+        group: &[usize],
+        tries: &mut Tries,
+    ) -> Result<(GroupPairing, BracketOutcome), Box<dyn Error>> {
+        if let Some((pairs, leftovers)) =
+            Self::pair_group(context, group, &[], Direction::Down, tries)?
+        {
+            return Ok(((pairs, leftovers), BracketOutcome::Paired));
+        }
+
+        let completion = context
+            .find_completion(group)
+            .ok_or("Couldn't pair the median group")?;
+
+        let pairs = completion
+            .iter()
+            .filter_map(|&(a, b)| Some((a.min(b?), a.max(b?))));
+        let leftovers = completion.iter().filter(|x| x.1.is_none()).map(|x| x.0);
+
+        Ok((
+            (pairs.collect(), leftovers.collect()),
+            BracketOutcome::Completed,
+        ))
+    }
+
+    fn bracket_trace(
+        context: &SwissContext,
+        residents: &[usize],
+        pairs: &[(usize, usize)],
+        floaters: &[usize],
+    ) -> BracketTrace {
+        let id = |x: usize| context.entrants[x].player.info.id;
+        let outcome = if pairs.is_empty() {
+            BracketOutcome::MovedDown
+        } else {
+            BracketOutcome::Paired
+        };
+
+        BracketTrace {
+            score: context.entrants[residents[0]].score,
+            residents: context.ids(residents),
+            outcome,
+            pairs: pairs.iter().map(|&(a, b)| (id(a), id(b))).collect(),
+            floaters: context.ids(floaters),
+            ..Default::default()
+        }
+    }
+
+    // Floaters come from the bottom of a group floating down and from the top of one floating
+    // up, preferring players who didn't float the same way in the previous round
+    fn pair_group(
+        context: &SwissContext,
+        group: &[usize],
+        rest: &[usize],
+        direction: Direction,
+        tries: &mut Tries,
+    ) -> Result<Option<GroupPairing>, Box<dyn Error>> {
+        let float = direction.float();
+        let candidates = match direction {
+            Direction::Down => group.iter().rev().copied().collect::<Vec<_>>(),
+            Direction::Up => group.to_vec(),
+        };
+        let candidates = candidates
+            .into_iter()
+            .sorted_by_key(|&x| context.entrants[x].player.last_float(1) == float)
+            .collect::<Vec<_>>();
+
+        let mut search = GroupSearch {
+            context,
+            score: group.last().map_or(0, |&x| context.entrants[x].score),
+            visited: 0,
+            tries,
+        };
+
+        for leftovers in (group.len() % 2..=group.len()).step_by(2) {
+            if rest.is_empty() && leftovers > 1 {
+                break;
+            }
+
+            for floaters in candidates.iter().copied().combinations(leftovers) {
+                search.visit()?;
+
+                let is_complete = if rest.is_empty() {
+                    floaters.iter().all(|&x| context.can_receive_bye(x))
+                } else {
+                    let lower = floaters.iter().chain(rest).copied().collect::<Vec<_>>();
+                    context.can_complete(&lower)
+                };
+
+                if !is_complete {
+                    search.tries.incomplete += 1;
+                    continue;
+                }
+
+                let remaining = group
+                    .iter()
+                    .copied()
+                    .filter(|x| !floaters.contains(x))
+                    .collect::<Vec<_>>();
+                let mut pairs = Vec::with_capacity(remaining.len() >> 1);
+
+                if search.pair_remaining(
+                    &remaining,
+                    &mut vec![false; remaining.len()],
+                    &mut pairs,
+                )? {
+                    return Ok(Some((pairs, floaters)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// Where a group's pairing falls short of the best conceivable one: more floaters than its parity
+// makes inevitable, and floaters who floated the same way last round when others could have
+fn shortfalls(
+    context: &SwissContext,
+    group: &[usize],
+    floaters: &[usize],
+    direction: Direction,
+) -> Vec<Shortfall> {
+    let repeats = |x: &&usize| context.entrants[**x].player.last_float(1) == direction.float();
+    let fresh = group.iter().filter(|x| !repeats(x)).count();
+
+    [
+        Shortfall::of("Floaters", floaters.len(), group.len() % 2),
+        Shortfall::of(
+            "Repeated floats",
+            floaters.iter().filter(repeats).count(),
+            floaters.len().saturating_sub(fresh),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+// Players who must both get the same colour never meet
+fn colors_compatible(_: &SwissContext<'_>, a: &Entrant<'_>, b: &Entrant<'_>) -> bool {
+    let (a, b) = (Due::of(a.player), Due::of(b.player));
+    !a.is_absolute || !b.is_absolute || a.color != b.color
+}
+
+// Colour allocation, in order: both due colours, the one that must be granted or the larger
+// imbalance, alternating from the last round the two had different colours, the due colour of
+// the higher ranked player. Players with no games go by pairing number and the initial colour.
+fn allocate_colors_with_rule(
+    context: &SwissContext,
+    high: usize,
+    low: usize,
+) -> (Color, Color, ColorRule) {
+    let (high_player, low_player) = (context.entrants[high].player, context.entrants[low].player);
+    let (high_due, low_due) = (Due::of(high_player), Due::of(low_player));
+
+    match (high_due.color, low_due.color) {
+        (Color::None, Color::None) => {}
+        (Color::None, color) => return (!color, color, ColorRule::BothPreferences),
+        (color, other) if color != other => return (color, !color, ColorRule::BothPreferences),
+        _ => {}
+    }
+
+    let strength = |x: Due| (x.is_absolute, x.imbalance);
+    if strength(high_due) != strength(low_due) {
+        let (high_color, low_color) = if strength(high_due) > strength(low_due) {
+            (high_due.color, !high_due.color)
+        } else {
+            (!low_due.color, low_due.color)
+        };
+
+        return (high_color, low_color, ColorRule::StrongerPreference);
+    }
+
+    let histories = high_player
+        .color_history
+        .iter()
+        .rev()
+        .zip(low_player.color_history.iter().rev());
+
+    for (&high_color, &low_color) in histories {
+        if high_color != low_color {
+            return (!high_color, !low_color, ColorRule::ColorHistory);
+        }
+    }
+
+    if high_due.color != Color::None {
+        return (
+            high_due.color,
+            !high_due.color,
+            ColorRule::HigherRankedPreference,
+        );
+    }
+
+    let initial_color = if high_player.pairing_number % 2 == 1 {
+        context.initial_color
+    } else {
+        !context.initial_color
+    };
+
+    (initial_color, !initial_color, ColorRule::PairingNumber)
+}
+
+struct GroupSearch<'c, 'a, 't> {
+    context: &'c SwissContext<'a>,
+    // The score group being paired, and the search steps spent on it
+    score: u8,
+    visited: usize,
+    tries: &'t mut Tries,
+}
+
+impl GroupSearch<'_, '_, '_> {
+    fn visit(&mut self) -> Result<(), Box<dyn Error>> {
+        self.visited += 1;
+        if self.visited > MAX_GROUP_NODES {
+            return Err(format!(
+                "Gave up on the score {} group after {MAX_GROUP_NODES} search steps",
+                self.score
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    // The top half meets the bottom half. An opponent due the other colour is preferred over
+    // the natural opponent in the bottom half, then anyone else in the group.
+    fn pair_remaining(
+        &mut self,
+        group: &[usize],
+        used: &mut [bool],
+        pairs: &mut Vec<(usize, usize)>,
+    ) -> Result<bool, Box<dyn Error>> {
+        self.visit()?;
+
+        let unpaired = (0..group.len())
+            .filter(|&i| !used[i])
+            .collect::<SmallVec<[usize; 32]>>();
+        let Some((&current, others)) = unpaired.split_first() else {
+            return Ok(true);
+        };
+
+        if others.is_empty() {
+            return Ok(false);
+        }
+
+        let due = |x: usize| Due::of(self.context.entrants[group[x]].player).color;
+        let half = unpaired.len() >> 1;
+        let wanted = due(current);
+        let partners = others[half - 1..]
+            .iter()
+            .chain(&others[..half - 1])
+            .enumerate()
+            .sorted_by_key(|&(position, &x)| {
+                let is_conflict = wanted != Color::None && due(x) == wanted;
+                (position >= others.len() - (half - 1), is_conflict, position)
+            })
+            .map(|(_, &x)| x)
+            .collect::<SmallVec<[usize; 32]>>();
+
+        used[current] = true;
+
+        for partner in partners {
+            if !self.context.compatible(group[current], group[partner]) {
+                self.tries.incompatible += 1;
+                continue;
+            }
+
+            used[partner] = true;
+            let (a, b) = (group[current], group[partner]);
+            pairs.push((a.min(b), a.max(b)));
+
+            if self.pair_remaining(group, used, pairs)? {
+                return Ok(true);
+            }
+
+            pairs.pop();
+            used[partner] = false;
+            self.tries.transpositions += 1;
+        }
+
+        used[current] = false;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{Due, colors_compatible};
+    use crate::acceleration::Acceleration;
+    use crate::chess_types::{Color, ColorPreference, Title};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::{Player, PlayerId};
+    use crate::swiss::{Entrant, SwissContext};
+    use crate::trf;
+
+    const RATINGS: [u16; 8] = [2400, 2350, 2300, 2250, 2200, 2150, 2100, 2050];
+
+    fn player(colors: &[Color]) -> Player {
+        let mut player = Player::new(1, "Player 1", Title::None, 2000);
+        for &color in colors {
+            player.push_color(color);
+        }
+        player
+    }
+
+    fn ids(players: &[(PlayerId, PlayerId)]) -> Vec<(PlayerId, PlayerId)> {
+        players.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect()
+    }
+
+    // After round 2 the groups are 4: {1}, 3: {3, 4}, 2: {2, 7}, 1: {5, 6} and 0: {8}
+    #[test]
+    fn upper_groups_go_down_lower_groups_go_up_median_last() {
+        let mut tournament = trf::replay(
+            &RATINGS,
+            &[
+                &[(5, 'w', '1'), (2, 'w', '1')],
+                &[(6, 'b', '1'), (1, 'b', '0')],
+                &[(7, 'w', '1'), (4, 'b', '=')],
+                &[(8, 'b', '1'), (3, 'w', '=')],
+                &[(1, 'b', '0'), (6, 'w', '=')],
+                &[(2, 'w', '0'), (5, 'b', '=')],
+                &[(3, 'b', '0'), (8, 'w', '1')],
+                &[(4, 'w', '0'), (7, 'b', '0')],
+            ],
+            5,
+            PairingSystemType::Lim,
+        );
+
+        let (_, trace) = tournament.pair_with_trace().unwrap();
+        let brackets = &trace.brackets;
+        assert_eq!(
+            brackets.iter().map(|x| x.score).collect::<Vec<_>>(),
+            [4, 3, 0, 1, 2]
+        );
+
+        // The bottom player floats down from an upper group
+        assert_eq!(brackets[0].floaters, [1]);
+        assert_eq!(brackets[1].downfloaters, [1]);
+        assert_eq!(ids(&brackets[1].pairs), [(1, 3)]);
+        assert_eq!(brackets[1].floaters, [4]);
+
+        // The top player floats up from a lower group
+        assert_eq!(brackets[2].floaters, [8]);
+        assert_eq!(brackets[3].upfloaters, [8]);
+        assert_eq!(ids(&brackets[3].pairs), [(6, 8)]);
+        assert_eq!(brackets[3].floaters, [5]);
+
+        let median = &brackets[4];
+        assert_eq!(median.residents, [2, 7]);
+        assert_eq!(median.downfloaters, [4]);
+        assert_eq!(median.upfloaters, [5]);
+        assert_eq!(ids(&median.pairs), [(4, 7), (2, 5)]);
+    }
+
+    // Everyone drew round 1. Group A is a win ahead for pairing round 2, yet every player is on
+    // half the points and so in the median group.
+    #[test]
+    fn median_group_goes_by_the_real_score() {
+        let mut tournament = trf::replay(
+            &RATINGS,
+            &[
+                &[(2, 'w', '=')],
+                &[(1, 'b', '=')],
+                &[(4, 'w', '=')],
+                &[(3, 'b', '=')],
+                &[(6, 'w', '=')],
+                &[(5, 'b', '=')],
+                &[(8, 'w', '=')],
+                &[(7, 'b', '=')],
+            ],
+            5,
+            PairingSystemType::Lim,
+        );
+        tournament.set_acceleration(Acceleration::Baku);
+
+        let (_, trace) = tournament.pair_with_trace().unwrap();
+        assert_eq!(trace.brackets.len(), 1);
+        assert_eq!(trace.brackets[0].residents, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // Whites of round 1 meet blacks, swapping the natural opponents of the bottom half
+        assert_eq!(
+            ids(&trace.brackets[0].pairs),
+            [(1, 6), (2, 5), (3, 8), (4, 7)]
+        );
+    }
+
+    #[test]
+    fn due_colour_evens_out_then_alternates() {
+        use Color::{Black, White};
+
+        let due = |colors: &[Color]| {
+            let due = Due::of(&player(colors));
+            (due.color, due.is_absolute)
+        };
+
+        assert_eq!(due(&[]), (Color::None, false));
+        assert_eq!(due(&[White, Black]), (White, false));
+        assert_eq!(due(&[White, Black, White]), (Black, false));
+        assert_eq!(due(&[White, White]), (Black, true));
+        assert_eq!(due(&[White, White, Black, Black]), (White, true));
+        assert_eq!(
+            due(&[White, Black, White, Black, White, White]),
+            (Black, true)
+        );
+    }
+
+    #[test]
+    fn players_who_must_take_the_same_colour_never_meet() {
+        use Color::{Black, White};
+
+        let tournament = trf::replay(&RATINGS[..2], &[&[], &[]], 5, PairingSystemType::Lim);
+        let context = SwissContext::new(&tournament, &BTreeSet::new());
+        let entrant = |player| Entrant {
+            player,
+            score: 0,
+            preference: ColorPreference::default(),
+            is_topscorer: false,
+        };
+
+        let twice_white = player(&[White, White]);
+        let more_whites = player(&[White, Black, White]);
+        let twice_black = player(&[Black, Black]);
+        let compatible = |a, b| colors_compatible(&context, &entrant(a), &entrant(b));
+
+        assert!(!compatible(&twice_white, &twice_white));
+        assert!(compatible(&twice_white, &more_whites));
+        assert!(compatible(&twice_white, &twice_black));
+    }
+}
@@ -0,0 +1,980 @@
+// Maximum weight matching in general graphs (Edmonds' blossom algorithm with dual variables,
+// O(n^3)), after Joris van Rantwijk's mwmatching.py and Galil's "Efficient algorithms for
+// finding maximum matching in graphs".
+
+#![allow(
+    clippy::many_single_char_names,
+    reason = "Keeps the notation of the reference implementation"
+)]
+
+use std::collections::HashMap;
+
+use crate::chess_types::Color;
+use crate::pairing::Pairing;
+use crate::player::PlayerId;
+
+pub type Weight = i64;
+
+// Stands in for "no vertex/edge/blossom" inside the solver
+const NONE: usize = usize::MAX;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatchingGraph {
+    players: Vec<PlayerId>,
+    indices: HashMap<PlayerId, usize>,
+    edges: Vec<(usize, usize, Weight)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Matching {
+    pub pairs: Vec<(PlayerId, PlayerId)>,
+    pub unmatched: Vec<PlayerId>,
+    pub weight: Weight,
+}
+
+impl MatchingGraph {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_players(players: impl IntoIterator<Item = PlayerId>) -> Self {
+        let mut graph = Self::new();
+        for player in players {
+            graph.add_player(player);
+        }
+
+        graph
+    }
+
+    #[inline]
+    pub fn add_player(&mut self, player: PlayerId) -> usize {
+        *self.indices.entry(player).or_insert_with(|| {
+            self.players.push(player);
+            self.players.len() - 1
+        })
+    }
+
+    // Unknown players are added on the fly. Self-loops are ignored.
+    #[inline]
+    pub fn add_edge(&mut self, a: PlayerId, b: PlayerId, weight: Weight) {
+        if a == b {
+            return;
+        }
+
+        let (a, b) = (self.add_player(a), self.add_player(b));
+        self.edges.push((a, b, weight));
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn maximum_weight_matching(&self) -> Matching {
+        self.solve(false)
+    }
+
+    // Heaviest matching among the ones with the most pairs
+    #[inline]
+    #[must_use]
+    pub fn maximum_cardinality_matching(&self) -> Matching {
+        self.solve(true)
+    }
+
+    fn solve(&self, max_cardinality: bool) -> Matching {
+        let mates = maximum_weight_matching(self.players.len(), &self.edges, max_cardinality);
+        let mut matching = Matching::default();
+
+        for (i, mate) in mates.iter().enumerate() {
+            match *mate {
+                Some(j) if i < j => matching.pairs.push((self.players[i], self.players[j])),
+                Some(_) => {}
+                None => matching.unmatched.push(self.players[i]),
+            }
+        }
+
+        // Parallel edges may exist, the heaviest one is the one used
+        matching.weight = self
+            .edges
+            .iter()
+            .filter(|&&(a, b, _)| mates[a] == Some(b))
+            .fold(HashMap::new(), |mut weights, &(a, b, weight)| {
+                let entry = weights.entry((a.min(b), a.max(b))).or_insert(weight);
+                *entry = (*entry).max(weight);
+                weights
+            })
+            .values()
+            .sum();
+
+        matching
+    }
+}
+
+impl Matching {
+    #[inline]
+    #[must_use]
+    pub const fn is_perfect(&self) -> bool {
+        self.unmatched.is_empty()
+    }
+
+    // Colours are left for the pairing system to allocate
+    #[inline]
+    pub fn into_pairings(self) -> Vec<Pairing> {
+        self.pairs
+            .into_iter()
+            .map(|(a, b)| Pairing::new(a, b, Color::None, Color::None))
+            .chain(self.unmatched.into_iter().map(Pairing::bye))
+            .collect()
+    }
+}
+
+// Index based entry point: vertices are 0..vertex_count, the result holds each vertex's mate
+#[must_use]
+pub fn maximum_weight_matching(
+    vertex_count: usize,
+    edges: &[(usize, usize, Weight)],
+    max_cardinality: bool,
+) -> Vec<Option<usize>> {
+    if edges.is_empty() {
+        return vec![None; vertex_count];
+    }
+
+    let mut solver = Solver::new(vertex_count, edges);
+    solver.run(max_cardinality);
+
+    solver
+        .mate
+        .iter()
+        .map(|&p| (p != NONE).then(|| solver.endpoint[p]))
+        .collect()
+}
+
+// Vertex dual variables are stored doubled so integer weights never need fractions.
+// Blossoms use ids nvertex..2*nvertex, endpoints are 2*edge and 2*edge+1.
+struct Solver<'e> {
+    nvertex: usize,
+    edges: &'e [(usize, usize, Weight)],
+    endpoint: Vec<usize>,
+    neighbend: Vec<Vec<usize>>,
+
+    mate: Vec<usize>,
+    label: Vec<u8>,
+    labelend: Vec<usize>,
+    inblossom: Vec<usize>,
+    blossomparent: Vec<usize>,
+    blossomchilds: Vec<Vec<usize>>,
+    blossombase: Vec<usize>,
+    blossomendps: Vec<Vec<usize>>,
+    bestedge: Vec<usize>,
+    blossombestedges: Vec<Option<Vec<usize>>>,
+    unusedblossoms: Vec<usize>,
+    dualvar: Vec<Weight>,
+    allowedge: Vec<bool>,
+    queue: Vec<usize>,
+}
+
+impl<'e> Solver<'e> {
+    fn new(vertex_count: usize, edges: &'e [(usize, usize, Weight)]) -> Self {
+        let nvertex = edges
+            .iter()
+            .fold(vertex_count, |n, &(i, j, _)| n.max(i + 1).max(j + 1));
+        let max_weight = edges.iter().map(|x| x.2).max().unwrap_or(0).max(0);
+
+        let endpoint = (0..2 * edges.len())
+            .map(|p| {
+                if p % 2 == 0 {
+                    edges[p / 2].0
+                } else {
+                    edges[p / 2].1
+                }
+            })
+            .collect();
+
+        let mut neighbend = vec![Vec::new(); nvertex];
+        for (k, &(i, j, _)) in edges.iter().enumerate() {
+            neighbend[i].push(2 * k + 1);
+            neighbend[j].push(2 * k);
+        }
+
+        Self {
+            nvertex,
+            edges,
+            endpoint,
+            neighbend,
+            mate: vec![NONE; nvertex],
+            label: vec![0; 2 * nvertex],
+            labelend: vec![NONE; 2 * nvertex],
+            inblossom: (0..nvertex).collect(),
+            blossomparent: vec![NONE; 2 * nvertex],
+            blossomchilds: vec![Vec::new(); 2 * nvertex],
+            blossombase: (0..nvertex)
+                .chain(std::iter::repeat_n(NONE, nvertex))
+                .collect(),
+            blossomendps: vec![Vec::new(); 2 * nvertex],
+            bestedge: vec![NONE; 2 * nvertex],
+            blossombestedges: vec![None; 2 * nvertex],
+            unusedblossoms: (nvertex..2 * nvertex).collect(),
+            dualvar: std::iter::repeat_n(max_weight, nvertex)
+                .chain(std::iter::repeat_n(0, nvertex))
+                .collect(),
+            allowedge: vec![false; edges.len()],
+            queue: Vec::new(),
+        }
+    }
+
+    fn slack(&self, k: usize) -> Weight {
+        let (i, j, weight) = self.edges[k];
+        self.dualvar[i] + self.dualvar[j] - 2 * weight
+    }
+
+    fn blossom_leaves(&self, b: usize) -> Vec<usize> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![b];
+
+        while let Some(b) = stack.pop() {
+            if b < self.nvertex {
+                leaves.push(b);
+            } else {
+                stack.extend(self.blossomchilds[b].iter().rev());
+            }
+        }
+
+        leaves
+    }
+
+    fn assign_label(&mut self, w: usize, t: u8, p: usize) {
+        let b = self.inblossom[w];
+        self.label[w] = t;
+        self.label[b] = t;
+        self.labelend[w] = p;
+        self.labelend[b] = p;
+        self.bestedge[w] = NONE;
+        self.bestedge[b] = NONE;
+
+        if t == 1 {
+            let leaves = self.blossom_leaves(b);
+            self.queue.extend(leaves);
+        } else if t == 2 {
+            let base = self.blossombase[b];
+            let mate = self.mate[base];
+            self.assign_label(self.endpoint[mate], 1, mate ^ 1);
+        }
+    }
+
+    // Trace back from v and w to find either a new blossom (its base) or an augmenting path (NONE)
+    fn scan_blossom(&mut self, mut v: usize, mut w: usize) -> usize {
+        let mut path = Vec::new();
+        let mut base = NONE;
+
+        while v != NONE || w != NONE {
+            let mut b = self.inblossom[v];
+            if self.label[b] & 4 != 0 {
+                base = self.blossombase[b];
+                break;
+            }
+
+            path.push(b);
+            self.label[b] = 5;
+
+            if self.labelend[b] == NONE {
+                v = NONE;
+            } else {
+                v = self.endpoint[self.labelend[b]];
+                b = self.inblossom[v];
+                v = self.endpoint[self.labelend[b]];
+            }
+
+            if w != NONE {
+                std::mem::swap(&mut v, &mut w);
+            }
+        }
+
+        for b in path {
+            self.label[b] = 1;
+        }
+
+        base
+    }
+
+    fn add_blossom(&mut self, base: usize, k: usize) {
+        let (mut v, mut w, _) = self.edges[k];
+        let bb = self.inblossom[base];
+        let mut bv = self.inblossom[v];
+        let mut bw = self.inblossom[w];
+
+        let b = self.unusedblossoms.pop().expect("Ran out of blossom ids");
+        self.blossombase[b] = base;
+        self.blossomparent[b] = NONE;
+        self.blossomparent[bb] = b;
+
+        let mut path = Vec::new();
+        let mut endps = Vec::new();
+
+        while bv != bb {
+            self.blossomparent[bv] = b;
+            path.push(bv);
+            endps.push(self.labelend[bv]);
+            v = self.endpoint[self.labelend[bv]];
+            bv = self.inblossom[v];
+        }
+
+        path.push(bb);
+        path.reverse();
+        endps.reverse();
+        endps.push(2 * k);
+
+        while bw != bb {
+            self.blossomparent[bw] = b;
+            path.push(bw);
+            endps.push(self.labelend[bw] ^ 1);
+            w = self.endpoint[self.labelend[bw]];
+            bw = self.inblossom[w];
+        }
+
+        self.blossomchilds[b].clone_from(&path);
+        self.blossomendps[b] = endps;
+
+        self.label[b] = 1;
+        self.labelend[b] = self.labelend[bb];
+        self.dualvar[b] = 0;
+
+        for v in self.blossom_leaves(b) {
+            if self.label[self.inblossom[v]] == 2 {
+                self.queue.push(v);
+            }
+            self.inblossom[v] = b;
+        }
+
+        let mut bestedgeto = vec![NONE; 2 * self.nvertex];
+        for &bv in &path {
+            let neighbour_lists = match self.blossombestedges[bv].take() {
+                Some(list) => vec![list],
+                None => self
+                    .blossom_leaves(bv)
+                    .into_iter()
+                    .map(|v| self.neighbend[v].iter().map(|p| p / 2).collect())
+                    .collect(),
+            };
+
+            for list in neighbour_lists {
+                for k in list {
+                    let (mut i, mut j, _) = self.edges[k];
+                    if self.inblossom[j] == b {
+                        std::mem::swap(&mut i, &mut j);
+                    }
+
+                    let bj = self.inblossom[j];
+                    if bj != b
+                        && self.label[bj] == 1
+                        && (bestedgeto[bj] == NONE || self.slack(k) < self.slack(bestedgeto[bj]))
+                    {
+                        bestedgeto[bj] = k;
+                    }
+                }
+            }
+
+            self.bestedge[bv] = NONE;
+        }
+
+        let best_edges = bestedgeto
+            .into_iter()
+            .filter(|&k| k != NONE)
+            .collect::<Vec<_>>();
+        self.bestedge[b] = NONE;
+        for &k in &best_edges {
+            if self.bestedge[b] == NONE || self.slack(k) < self.slack(self.bestedge[b]) {
+                self.bestedge[b] = k;
+            }
+        }
+        self.blossombestedges[b] = Some(best_edges);
+    }
+
+    fn expand_blossom(&mut self, b: usize, endstage: bool) {
+        for s in self.blossomchilds[b].clone() {
+            self.blossomparent[s] = NONE;
+
+            if s < self.nvertex {
+                self.inblossom[s] = s;
+            } else if endstage && self.dualvar[s] == 0 {
+                self.expand_blossom(s, endstage);
+            } else {
+                for v in self.blossom_leaves(s) {
+                    self.inblossom[v] = s;
+                }
+            }
+        }
+
+        if !endstage && self.label[b] == 2 {
+            let childs = self.blossomchilds[b].clone();
+            let endps = self.blossomendps[b].clone();
+            let len = childs.len() as isize;
+            let at = |j: isize| j.rem_euclid(len) as usize;
+
+            let entrychild = self.inblossom[self.endpoint[self.labelend[b] ^ 1]];
+            let mut j = childs
+                .iter()
+                .position(|&x| x == entrychild)
+                .expect("Entry child") as isize;
+
+            let (jstep, endptrick) = if j & 1 == 1 {
+                j -= len;
+                (1, 0)
+            } else {
+                (-1, 1)
+            };
+
+            let mut p = self.labelend[b];
+            while j != 0 {
+                self.label[self.endpoint[p ^ 1]] = 0;
+                self.label[self.endpoint[endps[at(j - endptrick)] ^ endptrick as usize ^ 1]] = 0;
+                self.assign_label(self.endpoint[p ^ 1], 2, p);
+                self.allowedge[endps[at(j - endptrick)] / 2] = true;
+                j += jstep;
+                p = endps[at(j - endptrick)] ^ endptrick as usize;
+                self.allowedge[p / 2] = true;
+                j += jstep;
+            }
+
+            let bv = childs[at(j)];
+            self.label[self.endpoint[p ^ 1]] = 2;
+            self.label[bv] = 2;
+            self.labelend[self.endpoint[p ^ 1]] = p;
+            self.labelend[bv] = p;
+            self.bestedge[bv] = NONE;
+            j += jstep;
+
+            while childs[at(j)] != entrychild {
+                let bv = childs[at(j)];
+                if self.label[bv] == 1 {
+                    j += jstep;
+                    continue;
+                }
+
+                if let Some(v) = self
+                    .blossom_leaves(bv)
+                    .into_iter()
+                    .find(|&v| self.label[v] != 0)
+                {
+                    self.label[v] = 0;
+                    self.label[self.endpoint[self.mate[self.blossombase[bv]]]] = 0;
+                    self.assign_label(v, 2, self.labelend[v]);
+                }
+
+                j += jstep;
+            }
+        }
+
+        self.label[b] = 0;
+        self.labelend[b] = NONE;
+        self.blossomchilds[b].clear();
+        self.blossomendps[b].clear();
+        self.blossombase[b] = NONE;
+        self.blossombestedges[b] = None;
+        self.bestedge[b] = NONE;
+        self.unusedblossoms.push(b);
+    }
+
+    fn augment_blossom(&mut self, b: usize, v: usize) {
+        let mut t = v;
+        while self.blossomparent[t] != b {
+            t = self.blossomparent[t];
+        }
+
+        if t >= self.nvertex {
+            self.augment_blossom(t, v);
+        }
+
+        let len = self.blossomchilds[b].len() as isize;
+        let at = |j: isize| j.rem_euclid(len) as usize;
+
+        let i = self.blossomchilds[b]
+            .iter()
+            .position(|&x| x == t)
+            .expect("Child of blossom");
+        let mut j = i as isize;
+
+        let (jstep, endptrick) = if j & 1 == 1 {
+            j -= len;
+            (1, 0)
+        } else {
+            (-1, 1)
+        };
+
+        while j != 0 {
+            j += jstep;
+            let t = self.blossomchilds[b][at(j)];
+            let p = self.blossomendps[b][at(j - endptrick)] ^ endptrick as usize;
+
+            if t >= self.nvertex {
+                self.augment_blossom(t, self.endpoint[p]);
+            }
+
+            j += jstep;
+            let t = self.blossomchilds[b][at(j)];
+            if t >= self.nvertex {
+                self.augment_blossom(t, self.endpoint[p ^ 1]);
+            }
+
+            self.mate[self.endpoint[p]] = p ^ 1;
+            self.mate[self.endpoint[p ^ 1]] = p;
+        }
+
+        self.blossomchilds[b].rotate_left(i);
+        self.blossomendps[b].rotate_left(i);
+        self.blossombase[b] = self.blossombase[self.blossomchilds[b][0]];
+    }
+
+    fn augment_matching(&mut self, k: usize) {
+        let (v, w, _) = self.edges[k];
+
+        for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+            loop {
+                let bs = self.inblossom[s];
+                if bs >= self.nvertex {
+                    self.augment_blossom(bs, s);
+                }
+
+                self.mate[s] = p;
+                if self.labelend[bs] == NONE {
+                    break;
+                }
+
+                let t = self.endpoint[self.labelend[bs]];
+                let bt = self.inblossom[t];
+                s = self.endpoint[self.labelend[bt]];
+                let j = self.endpoint[self.labelend[bt] ^ 1];
+
+                if bt >= self.nvertex {
+                    self.augment_blossom(bt, j);
+                }
+
+                self.mate[j] = self.labelend[bt];
+                p = self.labelend[bt] ^ 1;
+            }
+        }
+    }
+
+    // Grows alternating trees from every free vertex; returns false once no augmenting path is left
+    fn stage(&mut self, max_cardinality: bool) -> bool {
+        self.label.fill(0);
+        self.bestedge.fill(NONE);
+        self.blossombestedges[self.nvertex..].fill(None);
+        self.allowedge.fill(false);
+        self.queue.clear();
+
+        for v in 0..self.nvertex {
+            if self.mate[v] == NONE && self.label[self.inblossom[v]] == 0 {
+                self.assign_label(v, 1, NONE);
+            }
+        }
+
+        loop {
+            while let Some(v) = self.queue.pop() {
+                for idx in 0..self.neighbend[v].len() {
+                    let p = self.neighbend[v][idx];
+                    let k = p / 2;
+                    let w = self.endpoint[p];
+
+                    if self.inblossom[v] == self.inblossom[w] {
+                        continue;
+                    }
+
+                    let mut kslack = 0;
+                    if !self.allowedge[k] {
+                        kslack = self.slack(k);
+                        if kslack <= 0 {
+                            self.allowedge[k] = true;
+                        }
+                    }
+
+                    if self.allowedge[k] {
+                        if self.label[self.inblossom[w]] == 0 {
+                            self.assign_label(w, 2, p ^ 1);
+                        } else if self.label[self.inblossom[w]] == 1 {
+                            let base = self.scan_blossom(v, w);
+                            if base == NONE {
+                                self.augment_matching(k);
+                                return true;
+                            }
+
+                            self.add_blossom(base, k);
+                        } else if self.label[w] == 0 {
+                            self.label[w] = 2;
+                            self.labelend[w] = p ^ 1;
+                        }
+                    } else if self.label[self.inblossom[w]] == 1 {
+                        let b = self.inblossom[v];
+                        if self.bestedge[b] == NONE || kslack < self.slack(self.bestedge[b]) {
+                            self.bestedge[b] = k;
+                        }
+                    } else if self.label[w] == 0
+                        && (self.bestedge[w] == NONE || kslack < self.slack(self.bestedge[w]))
+                    {
+                        self.bestedge[w] = k;
+                    }
+                }
+            }
+
+            if !self.update_duals(max_cardinality) {
+                return false;
+            }
+        }
+    }
+
+    // Returns false when the optimum has been reached
+    fn update_duals(&mut self, max_cardinality: bool) -> bool {
+        // 1: vertex dual hits zero, 2: free edge, 3: edge between two S-blossoms, 4: T-blossom dual
+        let mut delta_type = 0;
+        let mut delta = 0;
+        let mut delta_edge = NONE;
+        let mut delta_blossom = NONE;
+
+        if !max_cardinality {
+            delta_type = 1;
+            delta = *self.dualvar[..self.nvertex].iter().min().unwrap_or(&0);
+        }
+
+        for v in 0..self.nvertex {
+            if self.label[self.inblossom[v]] == 0 && self.bestedge[v] != NONE {
+                let d = self.slack(self.bestedge[v]);
+                if delta_type == 0 || d < delta {
+                    delta = d;
+                    delta_type = 2;
+                    delta_edge = self.bestedge[v];
+                }
+            }
+        }
+
+        for b in 0..2 * self.nvertex {
+            if self.blossomparent[b] == NONE && self.label[b] == 1 && self.bestedge[b] != NONE {
+                let d = self.slack(self.bestedge[b]) / 2;
+                if delta_type == 0 || d < delta {
+                    delta = d;
+                    delta_type = 3;
+                    delta_edge = self.bestedge[b];
+                }
+            }
+        }
+
+        for b in self.nvertex..2 * self.nvertex {
+            if self.blossombase[b] != NONE
+                && self.blossomparent[b] == NONE
+                && self.label[b] == 2
+                && (delta_type == 0 || self.dualvar[b] < delta)
+            {
+                delta = self.dualvar[b];
+                delta_type = 4;
+                delta_blossom = b;
+            }
+        }
+
+        if delta_type == 0 {
+            delta_type = 1;
+            delta = (*self.dualvar[..self.nvertex].iter().min().unwrap_or(&0)).max(0);
+        }
+
+        for v in 0..self.nvertex {
+            match self.label[self.inblossom[v]] {
+                1 => self.dualvar[v] -= delta,
+                2 => self.dualvar[v] += delta,
+                _ => {}
+            }
+        }
+
+        for b in self.nvertex..2 * self.nvertex {
+            if self.blossombase[b] != NONE && self.blossomparent[b] == NONE {
+                match self.label[b] {
+                    1 => self.dualvar[b] += delta,
+                    2 => self.dualvar[b] -= delta,
+                    _ => {}
+                }
+            }
+        }
+
+        match delta_type {
+            2 => {
+                self.allowedge[delta_edge] = true;
+                let (mut i, mut j, _) = self.edges[delta_edge];
+                if self.label[self.inblossom[i]] == 0 {
+                    std::mem::swap(&mut i, &mut j);
+                }
+                self.queue.push(i);
+            }
+            3 => {
+                self.allowedge[delta_edge] = true;
+                self.queue.push(self.edges[delta_edge].0);
+            }
+            4 => self.expand_blossom(delta_blossom, false),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn run(&mut self, max_cardinality: bool) {
+        for _ in 0..self.nvertex {
+            if !self.stage(max_cardinality) {
+                break;
+            }
+
+            for b in self.nvertex..2 * self.nvertex {
+                if self.blossomparent[b] == NONE
+                    && self.blossombase[b] != NONE
+                    && self.label[b] == 1
+                    && self.dualvar[b] == 0
+                {
+                    self.expand_blossom(b, true);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchingGraph, Weight, maximum_weight_matching};
+    use crate::random::Rng;
+
+    // Vertex 0 is left out, like in the reference implementation's tests
+    fn mates(edges: &[(usize, usize, Weight)], max_cardinality: bool) -> Vec<Option<usize>> {
+        let vertex_count = edges
+            .iter()
+            .map(|&(a, b, _)| a.max(b) + 1)
+            .max()
+            .unwrap_or(0);
+        maximum_weight_matching(vertex_count, edges, max_cardinality)
+    }
+
+    fn expected(mates: &[usize]) -> Vec<Option<usize>> {
+        mates.iter().map(|&x| (x != 0).then_some(x)).collect()
+    }
+
+    // (pairs, weight) of the best matching, trying every one
+    fn brute_force(
+        vertex_count: usize,
+        edges: &[(usize, usize, Weight)],
+        max_cardinality: bool,
+    ) -> (usize, Weight) {
+        fn search(
+            vertex: usize,
+            mated: &mut [bool],
+            edges: &[(usize, usize, Weight)],
+            max_cardinality: bool,
+        ) -> (usize, Weight) {
+            let Some(vertex) = (vertex..mated.len()).find(|&x| !mated[x]) else {
+                return (0, 0);
+            };
+
+            mated[vertex] = true;
+            let mut best = search(vertex + 1, mated, edges, max_cardinality);
+
+            for &(a, b, weight) in edges {
+                let other = if a == vertex {
+                    b
+                } else if b == vertex {
+                    a
+                } else {
+                    continue;
+                };
+                if mated[other] {
+                    continue;
+                }
+
+                mated[other] = true;
+                let (pairs, rest) = search(vertex + 1, mated, edges, max_cardinality);
+                let candidate = (pairs + 1, rest + weight);
+                mated[other] = false;
+
+                let better = if max_cardinality {
+                    candidate > best
+                } else {
+                    candidate.1 > best.1
+                };
+
+                if better {
+                    best = candidate;
+                }
+            }
+
+            mated[vertex] = false;
+            best
+        }
+
+        search(0, &mut vec![false; vertex_count], edges, max_cardinality)
+    }
+
+    fn score(mates: &[Option<usize>], edges: &[(usize, usize, Weight)]) -> (usize, Weight) {
+        for (i, mate) in mates.iter().enumerate() {
+            if let Some(j) = *mate {
+                assert_eq!(mates[j], Some(i), "The matching isn't symmetric");
+            }
+        }
+
+        let pairs = mates.iter().flatten().count() / 2;
+        let weight = mates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, mate)| {
+                let j = (*mate)?;
+                let heaviest = edges
+                    .iter()
+                    .filter(|&&(a, b, _)| (a, b) == (i, j) || (a, b) == (j, i))
+                    .map(|&(.., weight)| weight)
+                    .max();
+
+                (i < j).then(|| heaviest.expect("Matched along a missing edge"))
+            })
+            .sum();
+
+        (pairs, weight)
+    }
+
+    #[test]
+    fn small_graphs() {
+        assert_eq!(mates(&[], false), expected(&[]));
+        assert_eq!(mates(&[(0, 1, 1)], false), vec![Some(1), Some(0)]);
+        assert_eq!(
+            mates(&[(1, 2, 10), (2, 3, 11)], false),
+            expected(&[0, 0, 3, 2])
+        );
+        assert_eq!(
+            mates(&[(1, 2, 5), (2, 3, 11), (3, 4, 5)], false),
+            expected(&[0, 0, 3, 2, 0])
+        );
+        assert_eq!(
+            mates(&[(1, 2, 5), (2, 3, 11), (3, 4, 5)], true),
+            expected(&[0, 2, 1, 4, 3])
+        );
+    }
+
+    #[test]
+    fn negative_weights() {
+        let edges = [(1, 2, 2), (1, 3, -2), (2, 3, 1), (2, 4, -1), (3, 4, -6)];
+        assert_eq!(mates(&edges, false), expected(&[0, 2, 1, 0, 0]));
+        assert_eq!(mates(&edges, true), expected(&[0, 3, 4, 1, 2]));
+    }
+
+    #[test]
+    fn blossoms() {
+        // An S-blossom, then using it for an augmentation
+        let edges = [(1, 2, 8), (1, 3, 9), (2, 3, 10), (3, 4, 7)];
+        assert_eq!(mates(&edges, false), expected(&[0, 2, 1, 4, 3]));
+        let edges = [
+            (1, 2, 8),
+            (1, 3, 9),
+            (2, 3, 10),
+            (3, 4, 7),
+            (1, 6, 5),
+            (4, 5, 6),
+        ];
+        assert_eq!(mates(&edges, false), expected(&[0, 6, 3, 2, 5, 4, 1]));
+
+        // A T-blossom
+        let edges = [
+            (1, 2, 9),
+            (1, 3, 8),
+            (2, 3, 10),
+            (1, 4, 5),
+            (4, 5, 4),
+            (1, 6, 3),
+        ];
+        assert_eq!(mates(&edges, false), expected(&[0, 6, 3, 2, 5, 4, 1]));
+
+        // A nested S-blossom
+        let edges = [
+            (1, 2, 9),
+            (1, 3, 9),
+            (2, 3, 10),
+            (2, 4, 8),
+            (3, 5, 8),
+            (4, 5, 10),
+            (5, 6, 6),
+        ];
+        assert_eq!(mates(&edges, false), expected(&[0, 3, 4, 1, 2, 6, 5]));
+
+        // A nested S-blossom expanded after relabelling
+        let edges = [
+            (1, 2, 8),
+            (1, 3, 8),
+            (2, 3, 10),
+            (2, 4, 12),
+            (3, 5, 12),
+            (4, 5, 14),
+            (4, 6, 12),
+            (5, 7, 12),
+            (6, 7, 14),
+            (7, 8, 12),
+        ];
+        assert_eq!(mates(&edges, false), expected(&[0, 2, 1, 5, 6, 3, 4, 8, 7]));
+
+        // A T-blossom expanded
+        let edges = [
+            (1, 2, 23),
+            (1, 5, 22),
+            (1, 6, 15),
+            (2, 3, 25),
+            (3, 4, 22),
+            (4, 5, 25),
+            (4, 8, 14),
+            (5, 7, 13),
+        ];
+        assert_eq!(mates(&edges, false), expected(&[0, 6, 3, 2, 8, 7, 1, 5, 4]));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..500 {
+            let vertex_count = 2 + rng.rand::<u64>() as usize % 9;
+            let mut edges = Vec::new();
+
+            for a in 0..vertex_count {
+                for b in a + 1..vertex_count {
+                    if !rng.rand::<u64>().is_multiple_of(3) {
+                        edges.push((a, b, rng.rand::<u64>() as Weight % 21 - 5));
+                    }
+                }
+            }
+
+            for max_cardinality in [false, true] {
+                let mates = maximum_weight_matching(vertex_count, &edges, max_cardinality);
+                let (pairs, weight) = score(&mates, &edges);
+                let (best_pairs, best_weight) = brute_force(vertex_count, &edges, max_cardinality);
+
+                assert_eq!(weight, best_weight, "{edges:?}");
+                if max_cardinality {
+                    assert_eq!(pairs, best_pairs, "{edges:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn graph_of_players() {
+        let mut graph = MatchingGraph::with_players([10, 20, 30]);
+        graph.add_edge(10, 20, 3);
+        graph.add_edge(20, 10, 5);
+        graph.add_edge(20, 30, 4);
+        graph.add_edge(30, 30, 9);
+
+        let matching = graph.maximum_weight_matching();
+        assert_eq!(matching.pairs, vec![(10, 20)]);
+        assert_eq!(matching.unmatched, vec![30]);
+        assert_eq!(matching.weight, 5);
+        assert!(!matching.is_perfect());
+        assert_eq!(graph.edge_count(), 3);
+    }
+}
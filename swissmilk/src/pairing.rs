@@ -3,23 +3,27 @@ use crate::player::PlayerId;
 use crate::tournament::Tournament;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Pairing {
-    pub left: PlayerId,
-    pub right: Option<PlayerId>,
-
-    pub color_left: Color,
-    pub color_right: Color,
+pub enum Pairing {
+    Game {
+        left: PlayerId,
+        right: PlayerId,
+        color_left: Color,
+        color_right: Color,
+    },
+    // A player left without an opponent, who has no colour either
+    Bye(PlayerId),
 }
 
 impl Pairing {
     #[inline]
+    #[must_use]
     pub const fn new(
         left: PlayerId,
-        right: Option<PlayerId>,
+        right: PlayerId,
         color_left: Color,
         color_right: Color,
     ) -> Self {
-        Self {
+        Self::Game {
             left,
             right,
             color_left,
@@ -28,20 +32,116 @@ impl Pairing {
     }
 
     #[inline]
+    #[must_use]
+    pub const fn bye(player: PlayerId) -> Self {
+        Self::Bye(player)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_bye(&self) -> bool {
+        matches!(self, Self::Bye(_))
+    }
+
+    // The player with the bye for a bye
+    #[inline]
+    #[must_use]
+    pub const fn left(&self) -> PlayerId {
+        match *self {
+            Self::Game { left, .. } | Self::Bye(left) => left,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn right(&self) -> Option<PlayerId> {
+        match *self {
+            Self::Game { right, .. } => Some(right),
+            Self::Bye(_) => None,
+        }
+    }
+
+    // The colours of the left and the right player, None for a bye
+    #[inline]
+    #[must_use]
+    pub const fn colors(&self) -> Option<(Color, Color)> {
+        match *self {
+            Self::Game {
+                color_left,
+                color_right,
+                ..
+            } => Some((color_left, color_right)),
+            Self::Bye(_) => None,
+        }
+    }
+
+    // White first, a game without colours in the order of the pairing
+    #[inline]
+    #[must_use]
+    pub const fn white_and_black(&self) -> Option<(PlayerId, PlayerId)> {
+        match *self {
+            Self::Game {
+                left,
+                right,
+                color_left: Color::Black,
+                ..
+            } => Some((right, left)),
+            Self::Game { left, right, .. } => Some((left, right)),
+            Self::Bye(_) => None,
+        }
+    }
+
+    /// Difference of the pairing scores, 0 for a bye
+    ///
+    /// # Panics
+    /// When a player of the pairing isn't entered.
+    #[inline]
+    #[must_use]
     pub fn get_score_difference(&self, tournament: &Tournament) -> u8 {
-        if self.right.is_none() {
+        let Self::Game { left, right, .. } = *self else {
             return 0;
-        }
+        };
 
         let left = tournament
-            .get_player(self.left)
+            .get_player(left)
             .expect("Invalid player id/No player found");
-
-        // SAFETY: Already had returned if is none
         let right = tournament
-            .get_player(unsafe { self.right.unwrap_unchecked() })
+            .get_player(right)
             .expect("Invalid player id/No player found");
 
-        (i16::from(left.score) - i16::from(right.score)).unsigned_abs() as u8
+        tournament
+            .pairing_score(left)
+            .abs_diff(tournament.pairing_score(right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pairing;
+    use crate::chess_types::Color;
+
+    #[test]
+    fn byes_have_no_opponent_or_colours() {
+        let bye = Pairing::bye(3);
+
+        assert!(bye.is_bye());
+        assert_eq!(bye.left(), 3);
+        assert_eq!(bye.right(), None);
+        assert_eq!(bye.colors(), None);
+        assert_eq!(bye.white_and_black(), None);
+    }
+
+    #[test]
+    fn white_comes_first() {
+        let game = Pairing::new(1, 2, Color::Black, Color::White);
+
+        assert!(!game.is_bye());
+        assert_eq!((game.left(), game.right()), (1, Some(2)));
+        assert_eq!(game.colors(), Some((Color::Black, Color::White)));
+        assert_eq!(game.white_and_black(), Some((2, 1)));
+
+        // Colours aren't allocated yet
+        let game = Pairing::new(1, 2, Color::None, Color::None);
+        assert_eq!(game.white_and_black(), Some((1, 2)));
     }
 }
@@ -1,6 +1,7 @@
+use std::collections::BTreeSet;
 use std::error::Error;
 
-use crate::{pairing::Pairing, tournament::Tournament};
+use crate::{pairing::Pairing, player::PlayerId, tournament::Tournament, trace::PairingTrace};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 #[non_exhaustive]
@@ -11,10 +12,26 @@ pub enum PairingSystemType {
     DubovSwiss,
     BursteinSwiss,
     Lim,
+    Scheveningen,
 
     None,
 }
 
+// Every registered player stays in the tournament, `available` tells who's paired this round
 pub(crate) trait PairngSystem {
-    fn pair_round(tournament: &mut Tournament) -> Result<Vec<Pairing>, Box<dyn Error>>;
+    fn pair_round(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>>;
+
+    // Same pairings, explained. Systems without a trace of their own only list the boards.
+    fn pair_round_traced(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+        trace: &mut PairingTrace,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        let pairings = Self::pair_round(tournament, available)?;
+        trace.set_boards(&pairings);
+        Ok(pairings)
+    }
 }
@@ -1,8 +1,9 @@
-use crate::chess_types::{Color, ColorPreference, ColorPreferenceLevel, Title};
-use crate::random::{self, NewRandom};
+use crate::chess_types::{Color, ColorPreference, ColorPreferenceLevel, Float, Sex, Title};
+use crate::random::{NewRandom, Rng};
 use crate::tournament::Tournament;
 
 use std::cmp::Ordering;
+use std::error::Error;
 
 pub type PlayerId = u64;
 
@@ -12,13 +13,21 @@ pub struct PlayerInfo {
     pub name: String,
     pub rating: u16,
     pub title: Title,
+
+    pub fide_id: Option<u64>,
+    // Three letter FIDE code, e.g. "GEO"
+    pub federation: String,
+    pub club: String,
+    // YYYY/MM/DD, as in TRF
+    pub birth_date: String,
+    pub sex: Sex,
 }
 
-#[derive(Debug, Default, PartialEq)]
+// Players only refer to each other by id, whatever needs the rest of the event goes through the
+// tournament they are in
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
 #[non_exhaustive]
 pub struct Player {
-    belongs_to_tournament: Box<Tournament>,
-
     pub info: Box<PlayerInfo>,
 
     pub score: u8,
@@ -30,27 +39,30 @@ pub struct Player {
     pub pairing_number: u16,
     pub downfloats: u8,
     pub upfloats: u8,
-
-    pub history: Vec<PlayerId>,
+    pub float_history: Vec<Float>,
 }
 
 impl PlayerInfo {
     #[inline]
+    #[must_use]
     pub fn new(id: PlayerId, name: &str, title: Title, rating: u16) -> Self {
         Self {
             id,
             name: name.to_owned(),
             title,
             rating,
+            ..Default::default()
         }
     }
 
     #[inline]
+    #[must_use]
     pub fn as_string_csv_like(&self) -> String {
         format!("{} {} {:?} {}", self.id, self.name, self.title, self.rating)
     }
 
     #[inline]
+    #[must_use]
     pub const fn csv_header() -> &'static str {
         "id name title rating"
     }
@@ -59,23 +71,23 @@ impl PlayerInfo {
 impl PartialOrd for PlayerInfo {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.rating
-            .partial_cmp(&other.rating)
-            .map(|x| x.then(self.title.cmp(&other.title)))
-            .map(|x| x.then(self.name.cmp(&other.name).reverse()))
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for PlayerInfo {
     #[inline]
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other)
-            .expect("partial_cmp for PlayerInfo failed")
+        self.rating
+            .cmp(&other.rating)
+            .then(self.title.cmp(&other.title))
+            .then(self.name.cmp(&other.name).reverse())
     }
 }
 
 impl Player {
     #[inline]
+    #[must_use]
     pub fn new(id: PlayerId, name: &str, title: Title, rating: u16) -> Self {
         Self {
             info: Box::new(PlayerInfo::new(id, name, title, rating)),
@@ -83,6 +95,7 @@ impl Player {
         }
     }
 
+    #[must_use]
     pub fn get_dutch_color_preference(&self) -> ColorPreference {
         let chl = self.color_history.len();
 
@@ -125,12 +138,75 @@ impl Player {
         ColorPreference::new(Color::None, ColorPreferenceLevel::None)
     }
 
+    /// Players met over the board, in the order of the rounds
+    ///
+    /// # Errors
+    /// When an opponent isn't entered in `tournament`.
+    #[inline]
+    pub fn opponents<'a>(
+        &self,
+        tournament: &'a Tournament,
+    ) -> Result<Vec<&'a Self>, Box<dyn Error>> {
+        self.previous_opponents
+            .iter()
+            .map(|&id| {
+                tournament
+                    .get_player(id)
+                    .ok_or_else(|| format!("Opponent {id} isn't in the tournament").into())
+            })
+            .collect()
+    }
+
+    /// Current scores of the players met over the board
+    ///
+    /// # Errors
+    /// Like `opponents`.
+    #[inline]
+    pub fn opponent_scores(&self, tournament: &Tournament) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(self
+            .opponents(tournament)?
+            .into_iter()
+            .map(|x| x.score)
+            .collect())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn last_float(&self, rounds_ago: usize) -> Float {
+        self.float_history
+            .len()
+            .checked_sub(rounds_ago)
+            .map_or(Float::None, |i| self.float_history[i])
+    }
+
+    pub fn push_color(&mut self, color: Color) {
+        match color {
+            Color::White => self.color_difference += 1,
+            Color::Black => self.color_difference -= 1,
+            Color::None => return,
+        }
+
+        self.color_history.push(color);
+    }
+
+    pub fn push_float(&mut self, float: Float) {
+        match float {
+            Float::Down => self.downfloats += 1,
+            Float::Up => self.upfloats += 1,
+            Float::None => (),
+        }
+
+        self.float_history.push(float);
+    }
+
     #[inline]
+    #[must_use]
     pub fn as_string_csv_like(&self) -> String {
         format!("{} {}", self.info.as_string_csv_like(), self.score)
     }
 
     #[inline]
+    #[must_use]
     pub const fn csv_header() -> &'static str {
         "id name title rating score"
     }
@@ -140,27 +216,28 @@ const NAMES: [&str; 200] = include!("../../data/names.txt");
 
 impl NewRandom for PlayerInfo {
     #[inline]
-    fn new_random() -> Self {
-        let id = random::rand::<u64>();
-        let name = NAMES[random::rand::<u32>() as usize % 200].to_owned()
-            + &((random::rand::<u32>() % u32::from(u16::MAX)).to_string());
-        let rating = random::rand::<u32>() as u16 % 2000 + 1000;
-        let title = Title::try_from(random::rand::<u32>() as i32 % 9).expect("UNREACHABLE");
+    fn new_random(rng: &mut Rng) -> Self {
+        let id = rng.rand::<u64>();
+        let name = NAMES[rng.rand::<u32>() as usize % 200].to_owned()
+            + &((rng.rand::<u32>() % u32::from(u16::MAX)).to_string());
+        let rating = rng.rand::<u32>() as u16 % 2000 + 1000;
+        let title = Title::try_from(rng.rand::<u32>() as i32 % 9).expect("UNREACHABLE");
 
         Self {
             id,
             name,
             rating,
             title,
+            ..Default::default()
         }
     }
 }
 
 impl NewRandom for Player {
     #[inline]
-    fn new_random() -> Self {
+    fn new_random(rng: &mut Rng) -> Self {
         Self {
-            info: Box::new(PlayerInfo::new_random()),
+            info: Box::new(PlayerInfo::new_random(rng)),
             ..Default::default()
         }
     }
@@ -171,6 +248,52 @@ impl PartialOrd for Player {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         self.score
             .partial_cmp(&other.score)
-            .map(|x| x.then(self.pairing_number.cmp(&other.pairing_number)))
+            .map(|x| x.then(other.pairing_number.cmp(&self.pairing_number)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess_types::{GameResult, Title};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::Player;
+    use crate::tournament::Tournament;
+
+    #[test]
+    fn opponents_come_from_the_tournament() {
+        let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        for id in 1..=4 {
+            let rating = 2500 - 100 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        for pairing in tournament.pair().unwrap() {
+            tournament
+                .submit_result(&pairing, GameResult::LeftWins)
+                .unwrap();
+        }
+        tournament.close_round().unwrap();
+
+        let player = tournament.get_player(1).unwrap();
+        let opponent = player.previous_opponents[0];
+        let ids = player
+            .opponents(&tournament)
+            .unwrap()
+            .iter()
+            .map(|x| x.info.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [opponent]);
+        assert_eq!(player.opponent_scores(&tournament).unwrap(), [0]);
+
+        // Someone who was never entered isn't silently left out
+        let mut stranger = player.clone();
+        stranger.previous_opponents.push(9);
+        assert!(stranger.opponents(&tournament).is_err());
+        assert!(stranger.opponent_scores(&tournament).is_err());
     }
 }
@@ -7,12 +7,11 @@
 
 use std::num::Wrapping;
 use std::ops::{Add, BitAnd, Mul, Shr};
-use std::sync::atomic::Ordering;
-use std::sync::{LazyLock, atomic::AtomicUsize};
+use std::sync::{LazyLock, Mutex, PoisonError};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub trait NewRandom {
-    fn new_random() -> Self;
+    fn new_random(rng: &mut Rng) -> Self;
 }
 
 pub trait RandInt: Copy + Shr<Output = Self> + BitAnd<Output = Self> {
@@ -93,24 +92,135 @@ impl RandInt for u64 {
     }
 }
 
-static RAND_NEXT: LazyLock<AtomicUsize> = LazyLock::new(|| {
-    let now = SystemTime::now();
-    AtomicUsize::new(
-        now.duration_since(UNIX_EPOCH)
-            .expect("We're in the past?")
-            .as_millis() as usize,
-    )
-});
+// A linear congruential generator carrying its own state, so a seed always replays the same
+// sequence, e.g. to publish a drawing of lots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: usize,
+}
+
+impl Rng {
+    #[inline]
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            state: seed as usize,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn from_time() -> Self {
+        let now = SystemTime::now();
+        Self::new(
+            now.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        )
+    }
+
+    #[inline]
+    pub fn rand<T: RandInt>(&mut self) -> T
+    where
+        Wrapping<T>: Mul<Output = Wrapping<T>> + Add<Output = Wrapping<T>>,
+    {
+        let mut cur = Wrapping(T::from_usize(self.state));
+        cur = cur * (T::RAND_MULTIPLIER) + (T::RAND_INCREMENT);
+
+        self.state = cur.0.to_usize();
+
+        (cur.0 >> (T::RAND_SHIFT)) & T::RAND_MASK
+    }
+
+    // Uniform in [0, 1), from the 31 bits of a 64-bit draw
+    #[inline]
+    pub fn uniform(&mut self) -> f64 {
+        f64::from(self.rand::<u64>() as u32) / f64::from(1_u32 << 31)
+    }
 
+    // Fisher-Yates
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.rand::<u64>() as usize % (i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+static RAND_NEXT: LazyLock<Mutex<Rng>> = LazyLock::new(|| Mutex::new(Rng::from_time()));
+
+// Process-wide generator seeded from the clock, for when reproducibility doesn't matter
 #[inline]
 pub fn rand<T: RandInt>() -> T
 where
     Wrapping<T>: Mul<Output = Wrapping<T>> + Add<Output = Wrapping<T>>,
 {
-    let mut cur = Wrapping(T::from_usize(RAND_NEXT.load(Ordering::Relaxed)));
-    cur = cur * (T::RAND_MULTIPLIER) + (T::RAND_INCREMENT);
+    RAND_NEXT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .rand()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    // The sequence of the C runtime's rand() for the same seed
+    #[test]
+    fn seeds_replay_the_same_sequence() {
+        let mut rng = Rng::new(1);
+        let first = (0..3).map(|_| rng.rand::<u32>()).collect::<Vec<_>>();
+        assert_eq!(first, [41, 18_467, 6_334]);
+
+        let mut again = Rng::new(1);
+        assert!(first.iter().all(|&x| again.rand::<u32>() == x));
+
+        let mut other = Rng::new(2);
+        assert_ne!(
+            (0..3).map(|_| other.rand::<u32>()).collect::<Vec<_>>(),
+            first
+        );
+    }
 
-    RAND_NEXT.store(cur.0.to_usize(), Ordering::Relaxed);
+    #[test]
+    fn uniform_covers_the_unit_interval() {
+        let mut rng = Rng::new(9);
+        let draws = (0..1000).map(|_| rng.uniform()).collect::<Vec<_>>();
 
-    (cur.0 >> (T::RAND_SHIFT)) & T::RAND_MASK
+        assert!(draws.iter().all(|x| (0.0..1.0).contains(x)));
+        assert!(draws.iter().any(|&x| x < 0.1) && draws.iter().any(|&x| x > 0.9));
+
+        // Finer than the 15 bits of a 32-bit draw
+        assert!(draws.iter().any(|x| (x * 32_768.0).fract() != 0.0));
+    }
+
+    #[test]
+    fn clones_carry_on_alike() {
+        let mut rng = Rng::new(7);
+        rng.rand::<u64>();
+
+        let mut clone = rng.clone();
+        assert_eq!(clone, rng);
+        for _ in 0..10 {
+            assert_eq!(clone.rand::<u64>(), rng.rand::<u64>());
+        }
+    }
+
+    #[test]
+    fn shuffles_are_reproducible_permutations() {
+        let shuffled = |seed| {
+            let mut items = (0..20).collect::<Vec<_>>();
+            Rng::new(seed).shuffle(&mut items);
+            items
+        };
+
+        let items = shuffled(3);
+        assert_eq!(items, shuffled(3));
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        assert_ne!(items, shuffled(4));
+
+        let mut sorted = items;
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
 }
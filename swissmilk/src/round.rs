@@ -0,0 +1,316 @@
+use std::time::SystemTime;
+
+use crate::chess_types::{ByeValue, Color, GameResult, ResultScoreConfig};
+use crate::pairing::Pairing;
+use crate::player::PlayerId;
+
+// How a player came to be without an opponent
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Bye {
+    // The pairing-allocated bye (PAB), at most one per player
+    Allocated,
+    // Asked for before the round, or absent
+    Requested,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub board: u16,
+    pub pairing: Pairing,
+    pub result: Option<GameResult>,
+    pub bye: Option<Bye>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round {
+    pub number: u8,
+    pub games: Vec<Game>,
+
+    pub paired_at: SystemTime,
+    pub finished_at: Option<SystemTime>,
+}
+
+impl Game {
+    // A pairing without an opponent is the pairing-allocated bye
+    #[inline]
+    #[must_use]
+    pub const fn new(board: u16, pairing: Pairing, result: Option<GameResult>) -> Self {
+        let bye = if pairing.is_bye() {
+            Some(Bye::Allocated)
+        } else {
+            None
+        };
+
+        Self {
+            board,
+            pairing,
+            result,
+            bye,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn requested_bye(board: u16, player_id: PlayerId, result: GameResult) -> Self {
+        Self {
+            board,
+            pairing: Pairing::bye(player_id),
+            result: Some(result),
+            bye: Some(Bye::Requested),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn involves(&self, player_id: PlayerId) -> bool {
+        self.pairing.left() == player_id || self.pairing.right() == Some(player_id)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_bye(&self) -> bool {
+        self.pairing.is_bye()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_allocated_bye(&self) -> bool {
+        self.bye == Some(Bye::Allocated)
+    }
+
+    // Played over the board, byes and forfeits aren't
+    #[inline]
+    pub fn is_played(&self) -> bool {
+        !self.is_bye() && self.result.is_some_and(GameResult::is_played)
+    }
+
+    // The colour the player had, None unless the game was played
+    #[must_use]
+    pub fn color_of(&self, player_id: PlayerId) -> Option<Color> {
+        if !self.is_played() {
+            return None;
+        }
+
+        let (color_left, color_right) = self.pairing.colors()?;
+        if self.pairing.left() == player_id {
+            Some(color_left)
+        } else if self.pairing.right() == Some(player_id) {
+            Some(color_right)
+        } else {
+            None
+        }
+    }
+
+    // None for a bye or if the player isn't part of the game
+    #[must_use]
+    pub fn opponent_of(&self, player_id: PlayerId) -> Option<PlayerId> {
+        let right = self.pairing.right()?;
+
+        if self.pairing.left() == player_id {
+            Some(right)
+        } else if right == player_id {
+            Some(self.pairing.left())
+        } else {
+            None
+        }
+    }
+
+    // Points the player scored, once the result is final
+    #[must_use]
+    pub fn points_of(&self, player_id: PlayerId, result_scores: ResultScoreConfig) -> Option<u8> {
+        let (left, right) = self
+            .result
+            .filter(|x| !x.is_adjourned())?
+            .scores(result_scores);
+
+        if self.pairing.left() == player_id {
+            Some(left)
+        } else if self.pairing.right() == Some(player_id) {
+            Some(right)
+        } else {
+            None
+        }
+    }
+}
+
+impl Round {
+    // Boards are numbered from 1 in the order of the pairings, the bye scores `bye_value`
+    #[must_use]
+    pub fn new(number: u8, pairings: Vec<Pairing>, bye_value: ByeValue) -> Self {
+        let games = pairings
+            .into_iter()
+            .enumerate()
+            .map(|(i, pairing)| {
+                let result = pairing.is_bye().then_some(bye_value.result());
+                Game::new(i as u16 + 1, pairing, result)
+            })
+            .collect();
+
+        Self {
+            number,
+            games,
+            paired_at: SystemTime::now(),
+            finished_at: None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.finished_at.is_some()
+    }
+
+    // Adjourned games don't have their final result yet
+    #[inline]
+    #[must_use]
+    pub fn has_all_results(&self) -> bool {
+        self.games
+            .iter()
+            .all(|x| x.result.is_some_and(|x| !x.is_adjourned()))
+    }
+
+    #[inline]
+    pub fn adjourned_boards(&self) -> impl Iterator<Item = u16> {
+        self.games
+            .iter()
+            .filter(|x| x.result.is_some_and(GameResult::is_adjourned))
+            .map(|x| x.board)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_game(&self, board: u16) -> Option<&Game> {
+        self.games.iter().find(|x| x.board == board)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn game_of(&self, player_id: PlayerId) -> Option<&Game> {
+        self.games.iter().find(|x| x.involves(player_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bye, Game};
+    use crate::chess_types::{ByeValue, Color, GameResult, Title};
+    use crate::pairing::Pairing;
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::Player;
+    use crate::tournament::Tournament;
+
+    #[test]
+    fn games_know_their_players() {
+        let game = Game::new(
+            1,
+            Pairing::new(1, 2, Color::Black, Color::White),
+            Some(GameResult::LeftWins),
+        );
+
+        assert_eq!(game.bye, None);
+        assert_eq!(
+            (game.opponent_of(1), game.opponent_of(2)),
+            (Some(2), Some(1))
+        );
+        assert_eq!(game.opponent_of(3), None);
+        assert_eq!(
+            (game.color_of(1), game.color_of(2)),
+            (Some(Color::Black), Some(Color::White))
+        );
+        assert_eq!(
+            (game.points_of(1, (2, 1, 0)), game.points_of(2, (2, 1, 0))),
+            (Some(2), Some(0))
+        );
+
+        // Forfeits score, but aren't played
+        let forfeit = Game::new(
+            1,
+            Pairing::new(1, 2, Color::White, Color::Black),
+            Some(GameResult::RightWinsByForfeit),
+        );
+        assert!(!forfeit.is_played());
+        assert_eq!(forfeit.color_of(1), None);
+        assert_eq!(forfeit.points_of(2, (2, 1, 0)), Some(2));
+    }
+
+    // Five players left after a bye on request, one of them gets the pairing-allocated bye
+    #[test]
+    fn allocated_and_requested_byes() {
+        let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        for id in 1..=6 {
+            let rating = 2500 - 100 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+        tournament.start().unwrap();
+        tournament.request_bye(6, 1, ByeValue::Draw).unwrap();
+
+        let pairings = tournament.pair().unwrap();
+        let round = tournament.get_round(1).unwrap();
+
+        let allocated = round.games.iter().find(|x| x.is_allocated_bye()).unwrap();
+        assert_eq!(allocated.bye, Some(Bye::Allocated));
+        assert_eq!(allocated.result, Some(GameResult::LeftWins));
+        assert!(pairings.contains(&allocated.pairing));
+
+        let requested = round.game_of(6).unwrap();
+        assert_eq!(requested.bye, Some(Bye::Requested));
+        assert!(!requested.is_allocated_bye());
+        assert_eq!(requested.result, Some(GameResult::Draw));
+        assert!(!pairings.contains(&requested.pairing));
+
+        assert!(requested.is_bye() && allocated.is_bye());
+        assert!(!requested.is_played() && !allocated.is_played());
+    }
+
+    #[test]
+    fn games_results_and_opponents_by_round() {
+        let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        for id in 1..=4 {
+            let rating = 2500 - 100 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        let pairings = tournament.pair().unwrap();
+        let (first, second) = (&pairings[0], &pairings[1]);
+        let (left, right) = (first.left(), first.right().unwrap());
+
+        assert_eq!(tournament.opponent_in_round(left, 1), Some(right));
+        assert_eq!(tournament.opponent_in_round(right, 1), Some(left));
+        assert_eq!(tournament.opponent_in_round(left, 2), None);
+        assert_eq!(tournament.opponent_in_round(7, 1), None);
+
+        // Only games with a final result count
+        assert_eq!(tournament.result_of(1, 1), None);
+        assert_eq!(tournament.games_of_player(left).count(), 0);
+
+        tournament
+            .submit_result(first, GameResult::LeftWins)
+            .unwrap();
+        tournament
+            .submit_result(second, GameResult::Adjourned)
+            .unwrap();
+
+        assert_eq!(tournament.result_of(1, 1), Some(GameResult::LeftWins));
+        assert_eq!(tournament.result_of(1, 2), Some(GameResult::Adjourned));
+        assert_eq!(tournament.result_of(1, 3), None);
+        assert_eq!(tournament.result_of(2, 1), None);
+
+        let games = tournament.games_of_player(right).collect::<Vec<_>>();
+        assert_eq!(games.len(), 1);
+        assert_eq!((games[0].0, &games[0].1.pairing), (1, first));
+        assert_eq!(tournament.games_of_player(second.left()).count(), 0);
+
+        tournament.resolve_adjourned(2, GameResult::Draw).unwrap();
+        assert_eq!(tournament.games_of_player(second.left()).count(), 1);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use crate::chess_types::{ByeValue, GameResult, Team};
+use crate::pairing::Pairing;
+use crate::pairing_system::PairingSystemType;
+use crate::player::Player;
+use crate::random::{NewRandom as _, Rng};
+use crate::tournament::Tournament;
+use crate::trf;
+
+// Random Tournament Generator, simulates whole events the way FIDE's endorsement procedure
+// asks for. Probabilities are between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorConfig {
+    pub player_count: usize,
+    pub number_of_rounds: u8,
+    pub pairing_system: PairingSystemType,
+
+    // Chance of a draw between two equally rated players
+    pub draw_rate: f64,
+    // Chance of a game being forfeited, a tenth of those are double forfeits
+    pub forfeit_rate: f64,
+    // Chance of a player withdrawing before a round, for the rest of the event
+    pub withdrawal_rate: f64,
+    // Chance of a player asking for a half point bye before a round
+    pub half_point_bye_rate: f64,
+}
+
+impl Default for GeneratorConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            player_count: 40,
+            number_of_rounds: 9,
+            pairing_system: PairingSystemType::ClassicSwiss,
+            draw_rate: 0.3,
+            forfeit_rate: 0.01,
+            withdrawal_rate: 0.005,
+            half_point_bye_rate: 0.01,
+        }
+    }
+}
+
+/// The same seed generates the same tournament
+///
+/// # Errors
+/// When a round can't be paired or its results can't be recorded.
+pub fn generate(config: &GeneratorConfig, rng: &mut Rng) -> Result<Tournament, Box<dyn Error>> {
+    generate_with(config, rng, |_| Ok(()))
+}
+
+// `before_pairing` sees the tournament just before each round is paired
+fn generate_with(
+    config: &GeneratorConfig,
+    rng: &mut Rng,
+    mut before_pairing: impl FnMut(&Tournament) -> Result<(), Box<dyn Error>>,
+) -> Result<Tournament, Box<dyn Error>> {
+    let mut tournament = Tournament::new(config.number_of_rounds, config.pairing_system);
+
+    let mut ids = BTreeSet::new();
+    while ids.len() < config.player_count {
+        let player = Player::new_random(rng);
+        if ids.insert(player.info.id) {
+            tournament.add_player(player);
+        }
+    }
+
+    // Lots for a round-robin come from the seed too
+    if config.pairing_system == PairingSystemType::BergerTable {
+        tournament.draw_lots(rng);
+    }
+
+    // The players of a Scheveningen take turns joining team A and team B
+    if config.pairing_system == PairingSystemType::Scheveningen {
+        for (i, &id) in ids.iter().enumerate() {
+            let team = if i % 2 == 0 { Team::A } else { Team::B };
+            tournament.assign_team(id, team)?;
+        }
+    }
+
+    // Fixed tables don't take byes on request
+    let swiss = !matches!(
+        config.pairing_system,
+        PairingSystemType::BergerTable | PairingSystemType::Scheveningen
+    );
+
+    for round in 1..=config.number_of_rounds {
+        // Nobody misses the first round, so every player gets a pairing number
+        if round > 1 {
+            for &id in &ids {
+                if tournament.is_withdrawn(id) {
+                    continue;
+                }
+
+                if rng.uniform() < config.withdrawal_rate {
+                    tournament.withdraw(id)?;
+                } else if rng.uniform() < config.half_point_bye_rate && swiss {
+                    tournament.request_bye(id, round, ByeValue::Draw)?;
+                }
+            }
+        }
+
+        before_pairing(&tournament)?;
+        let pairings = tournament.pair()?;
+
+        for pairing in &pairings {
+            let Pairing::Game { left, right, .. } = *pairing else {
+                continue;
+            };
+
+            // Withdrawn players forfeit their games on fixed tables
+            if tournament.is_withdrawn(left) || tournament.is_withdrawn(right) {
+                continue;
+            }
+
+            let (Some(left), Some(right)) =
+                (tournament.get_player(left), tournament.get_player(right))
+            else {
+                return Err("The pairing has an unknown player".into());
+            };
+
+            let result = simulate_game(config, rng, left.info.rating, right.info.rating);
+            tournament.submit_result(pairing, result)?;
+        }
+
+        tournament.close_round()?;
+    }
+
+    Ok(tournament)
+}
+
+/// Generates a tournament like `generate` and writes it as TRF16
+///
+/// # Errors
+/// Like `generate` and `trf::write`.
+#[inline]
+pub fn generate_trf(config: &GeneratorConfig, rng: &mut Rng) -> Result<String, Box<dyn Error>> {
+    trf::write(&generate(config, rng)?)
+}
+
+// Elo expected score, with the draws taken evenly out of both sides' winning chances
+fn simulate_game(
+    config: &GeneratorConfig,
+    rng: &mut Rng,
+    left_rating: u16,
+    right_rating: u16,
+) -> GameResult {
+    if rng.uniform() < config.forfeit_rate {
+        let roll = rng.uniform();
+
+        return if roll < 0.1 {
+            GameResult::DoubleForfeit
+        } else if roll < 0.55 {
+            GameResult::LeftWinsByForfeit
+        } else {
+            GameResult::RightWinsByForfeit
+        };
+    }
+
+    let difference = f64::from(right_rating) - f64::from(left_rating);
+    let expected = 1.0 / (1.0 + 10f64.powf(difference / 400.0));
+
+    let draw = config.draw_rate.min(2.0 * expected.min(1.0 - expected));
+    let win = expected - draw / 2.0;
+
+    let roll = rng.uniform();
+    if roll < win {
+        GameResult::LeftWins
+    } else if roll < win + draw {
+        GameResult::Draw
+    } else {
+        GameResult::RightWins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GeneratorConfig, generate, generate_with};
+    use crate::checker::check;
+    use crate::pairing_system::PairingSystemType;
+    use crate::random::Rng;
+    use crate::trf;
+
+    const SWISS: [PairingSystemType; 4] = [
+        PairingSystemType::ClassicSwiss,
+        PairingSystemType::DubovSwiss,
+        PairingSystemType::BursteinSwiss,
+        PairingSystemType::Lim,
+    ];
+
+    // Busy enough for forfeits, withdrawals and byes on request to happen
+    fn swiss(pairing_system: PairingSystemType) -> GeneratorConfig {
+        GeneratorConfig {
+            player_count: 15,
+            number_of_rounds: 6,
+            pairing_system,
+            forfeit_rate: 0.05,
+            withdrawal_rate: 0.02,
+            half_point_bye_rate: 0.05,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn seeds_replay_swiss_events() {
+        for system in SWISS {
+            let config = swiss(system);
+            let tournament = generate(&config, &mut Rng::new(8)).unwrap();
+            let again = generate(&config, &mut Rng::new(8)).unwrap();
+            let other = generate(&config, &mut Rng::new(9)).unwrap();
+
+            let trf = trf::write(&tournament).unwrap();
+            assert_eq!(trf::write(&again).unwrap(), trf, "{system:?}");
+            assert_ne!(trf::write(&other).unwrap(), trf, "{system:?}");
+            assert_eq!(tournament.get_rounds().len(), 6);
+        }
+    }
+
+    #[test]
+    fn every_generated_round_passes_the_checker() {
+        for system in SWISS {
+            let mut rounds = 0;
+            generate_with(&swiss(system), &mut Rng::new(21), |tournament| {
+                // The checker wants pairing numbers, which the first round gives out
+                let mut tournament = tournament.clone();
+                if !tournament.has_started() {
+                    tournament.start()?;
+                }
+
+                let pairings = tournament.clone().pair()?;
+                let report = check(&tournament, &pairings)?;
+                assert!(
+                    report.is_valid() && !report.engine_differs,
+                    "{system:?} round {}: {:?}",
+                    tournament.current_round(),
+                    report.violations
+                );
+
+                rounds += 1;
+                Ok(())
+            })
+            .unwrap();
+
+            assert_eq!(rounds, 6, "{system:?}");
+        }
+    }
+
+    // Withdrawn players keep their place on the table and forfeit the rest of their games
+    #[test]
+    fn withdrawals_from_a_round_robin() {
+        let config = GeneratorConfig {
+            player_count: 8,
+            number_of_rounds: 7,
+            pairing_system: PairingSystemType::BergerTable,
+            withdrawal_rate: 0.1,
+            ..Default::default()
+        };
+
+        let tournament = generate(&config, &mut Rng::new(5)).unwrap();
+        let again = generate(&config, &mut Rng::new(5)).unwrap();
+        assert_eq!(
+            trf::write(&again).unwrap(),
+            trf::write(&tournament).unwrap()
+        );
+        assert!(!tournament.withdrawn_players().is_empty());
+
+        for &(id, from) in tournament.withdrawn_players() {
+            for round in tournament.get_rounds().iter().filter(|x| x.number >= from) {
+                let result = round.game_of(id).and_then(|x| x.result).unwrap();
+                assert!(!result.is_played(), "Player {id} in round {}", round.number);
+            }
+        }
+    }
+}
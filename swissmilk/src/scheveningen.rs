@@ -0,0 +1,335 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use itertools::Itertools as _;
+
+use crate::chess_types::{Color, Team};
+use crate::pairing::Pairing;
+use crate::pairing_system::PairngSystem;
+use crate::player::PlayerId;
+use crate::tournament::Tournament;
+
+pub(crate) struct ScheveningenPairingSystem;
+
+// A board of a Scheveningen table: the board of team A, the board of team B it meets, and the
+// colour team A's player gets. Boards start from 1.
+pub type ScheveningenBoard = (usize, usize, Color);
+
+// Every round of the tables for 2 to 10 players a side, board by board of team A: the board of
+// team B it meets and the colour it gets. Round 1 pairs equal boards with alternating colours.
+// Every player of either team gets as many whites as blacks as far as possible and never the
+// same colour three times in a row, each team gets half the whites of every round (two a side
+// can't have both, the players come first) and team A the extra white of an odd table.
+const TABLES: [&[&str]; 9] = [
+    &["1W 2W", "2B 1B"],
+    &["1W 2B 3W", "2B 3W 1B", "3B 1W 2W"],
+    &["1W 2B 3W 4B", "2B 3W 4B 1W", "3B 4W 1B 2W", "4W 1B 2W 3B"],
+    &[
+        "1W 2B 3W 4B 5W",
+        "2B 3W 4B 5W 1B",
+        "3B 4W 5B 1B 2W",
+        "4W 5B 1W 2W 3B",
+        "5W 1W 2B 3W 4B",
+    ],
+    &[
+        "1W 2B 3W 4B 5W 6B",
+        "2B 3W 4B 6W 1B 5W",
+        "3B 4W 1W 5B 6B 2W",
+        "4W 6B 5B 2W 3W 1B",
+        "6W 5W 2B 1W 4B 3B",
+        "5B 1B 6W 3B 2W 4W",
+    ],
+    &[
+        "1W 2B 3W 4B 5W 6B 7W",
+        "2B 3W 4B 5W 6B 7W 1B",
+        "3B 4W 5B 6W 7B 1B 2W",
+        "4W 5B 6W 7B 1W 2W 3B",
+        "5W 6B 7W 1B 2B 3W 4B",
+        "6B 7W 1B 2W 3W 4B 5W",
+        "7B 1W 2W 3B 4W 5B 6W",
+    ],
+    &[
+        "1W 2B 3W 4B 5W 6B 7W 8B",
+        "2B 3W 4B 5W 6B 7W 8B 1W",
+        "3B 4W 5B 6W 7B 8W 1B 2W",
+        "4W 5B 6W 7B 8W 1B 2W 3B",
+        "5W 6B 7W 8B 1W 2B 3W 4B",
+        "6B 7W 8B 1W 2B 3W 4B 5W",
+        "7B 8W 1B 2W 3B 4W 5B 6W",
+        "8W 1B 2W 3B 4W 5B 6W 7B",
+    ],
+    &[
+        "1W 2B 3W 4B 5W 6B 7W 8B 9W",
+        "2B 3W 4B 5W 6B 7W 8B 9W 1B",
+        "3B 4W 5B 6W 7B 8W 9B 1B 2W",
+        "4W 5B 6W 7B 8W 9B 1W 2W 3B",
+        "5W 6B 7W 8B 9W 1B 2B 3W 4B",
+        "6B 7W 8B 9W 1B 2W 3B 4B 5W",
+        "7B 9B 1W 2B 3W 4W 6W 5B 8W",
+        "9W 8W 2W 1W 4B 3B 5B 6W 7B",
+        "8B 1B 9B 3W 2W 5W 4W 7W 6B",
+    ],
+    &[
+        "1W 2B 3W 4B 5W 6B 7W 8B 9W 10B",
+        "4B 9W 1B 2W 3B 10W 6B 7W 5B 8W",
+        "8W 10B 4W 1B 9B 3B 2W 5W 6W 7B",
+        "10B 7W 8B 6W 4W 2B 3W 9B 1W 5B",
+        "7W 5B 2W 10W 1B 8W 4B 6B 3B 9W",
+        "3B 6W 7B 9B 8B 1W 5W 2W 10B 4W",
+        "5W 4B 6W 3W 10W 7B 9B 1B 8W 2B",
+        "9W 1W 10B 5B 7W 4W 8B 3B 2W 6B",
+        "6B 3W 5B 8W 2B 9B 10W 4W 7B 1W",
+        "2B 8B 9W 7B 6W 5W 1B 10W 4B 3W",
+    ],
+];
+
+impl PairngSystem for ScheveningenPairingSystem {
+    fn pair_round(
+        tournament: &mut Tournament,
+        available: &BTreeSet<PlayerId>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        if !tournament.has_started() {
+            tournament.start()?;
+        }
+
+        if tournament.current_round() > tournament.number_of_rounds() {
+            return Err(format!(
+                "The Scheveningen is over after round {}",
+                tournament.number_of_rounds()
+            )
+            .into());
+        }
+
+        // Boards go by pairing number within each team
+        let mut home = Vec::new();
+        let mut away = Vec::new();
+        for player in tournament
+            .get_players()
+            .values()
+            .filter(|x| available.contains(&x.info.id))
+            .sorted_by_key(|x| x.pairing_number)
+        {
+            match tournament.team_of(player.info.id) {
+                Some(Team::A) => home.push(player),
+                Some(Team::B) => away.push(player),
+                None => return Err(format!("Player {} has no team", player.info.id).into()),
+            }
+        }
+
+        if home.len() != away.len() {
+            return Err("Both teams of a Scheveningen need the same number of players".into());
+        }
+
+        let team_size = home.len();
+        let Some(table) = scheveningen_table(team_size) else {
+            return Err("Scheveningen tables go from 2 to 10 players a side".into());
+        };
+
+        // Every cycle plays the whole table, the odd ones (second, fourth...) with colours reversed
+        let cycles = usize::from(tournament.cycles());
+        if usize::from(tournament.number_of_rounds()) != team_size * cycles {
+            return Err(format!(
+                "A Scheveningen of {team_size} players a side over {cycles} cycle(s) takes {} \
+                 rounds, not {}",
+                team_size * cycles,
+                tournament.number_of_rounds()
+            )
+            .into());
+        }
+
+        let index = usize::from(tournament.current_round() - 1);
+        let (cycle, round) = (index / team_size, index % team_size);
+        let reverse = (cycle % 2 == 1) != (tournament.initial_color() == Color::Black);
+
+        let pairings = table[round]
+            .iter()
+            .map(|&(a, b, color)| {
+                let color = if reverse { !color } else { color };
+                Pairing::new(home[a - 1].info.id, away[b - 1].info.id, color, !color)
+            })
+            .collect();
+
+        Ok(pairings)
+    }
+}
+
+// The Scheveningen table for `team_size` players a side, round by round
+#[must_use]
+pub fn scheveningen_table(team_size: usize) -> Option<Vec<Vec<ScheveningenBoard>>> {
+    let rounds = TABLES.get(team_size.checked_sub(2)?)?;
+
+    let table = rounds
+        .iter()
+        .map(|round| {
+            round
+                .split(' ')
+                .enumerate()
+                .map(|(board, game)| {
+                    let (opponent, color) = game.split_at(game.len() - 1);
+                    let color = if color == "W" {
+                        Color::White
+                    } else {
+                        Color::Black
+                    };
+
+                    (board + 1, opponent.parse().unwrap_or_default(), color)
+                })
+                .collect()
+        })
+        .collect();
+
+    Some(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::scheveningen_table;
+    use crate::chess_types::{Color, GameResult, Team, Title};
+    use crate::pairing::Pairing;
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::{Player, PlayerId};
+    use crate::tournament::Tournament;
+
+    // Players 1 to 2n by rating, the top half in team B, so team A isn't the team of the top seed
+    fn scheveningen(team_size: u64) -> Tournament {
+        let mut tournament = Tournament::new(team_size as u8, PairingSystemType::Scheveningen);
+        for id in 1..=2 * team_size {
+            let rating = 2500 - 10 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        for id in 1..=2 * team_size {
+            let team = if id > team_size { Team::A } else { Team::B };
+            tournament.assign_team(id, team).unwrap();
+        }
+
+        tournament
+    }
+
+    // Whites of a player's colours, which mustn't repeat three times in a row
+    fn whites(colors: &[Color]) -> usize {
+        assert!(
+            colors.windows(3).all(|x| x[0] != x[1] || x[1] != x[2]),
+            "{colors:?}"
+        );
+        colors.iter().filter(|&&x| x == Color::White).count()
+    }
+
+    #[test]
+    fn tables_are_balanced_for_both_teams() {
+        for team_size in 2..=10 {
+            let table = scheveningen_table(team_size).unwrap();
+            let (fewest, most) = (team_size / 2, team_size.div_ceil(2));
+
+            let mut home = vec![Vec::new(); team_size];
+            let mut away = vec![Vec::new(); team_size];
+            let mut games = vec![vec![0; team_size]; team_size];
+
+            for (round, boards) in table.iter().enumerate() {
+                assert_eq!(boards.len(), team_size);
+                let mut home_whites = 0;
+
+                for &(a, b, color) in boards {
+                    home[a - 1].push(color);
+                    away[b - 1].push(!color);
+                    games[a - 1][b - 1] += 1;
+                    home_whites += usize::from(color == Color::White);
+
+                    // Round 1 pairs equal boards with alternating colours
+                    if round == 0 && team_size > 2 {
+                        let expected = if a % 2 == 1 {
+                            Color::White
+                        } else {
+                            Color::Black
+                        };
+                        assert_eq!((a, color), (b, expected), "{team_size} a side");
+                    }
+                }
+
+                if team_size > 2 {
+                    assert!(
+                        (fewest..=most).contains(&home_whites),
+                        "Round {} of {team_size} a side",
+                        round + 1
+                    );
+                }
+            }
+
+            assert!(
+                games.iter().flatten().all(|&x| x == 1),
+                "{team_size} a side"
+            );
+
+            for colors in home.iter().chain(&away) {
+                assert!(
+                    (fewest..=most).contains(&whites(colors)),
+                    "{team_size} a side"
+                );
+            }
+
+            let home_whites = home.iter().map(|x| whites(x)).sum::<usize>();
+            assert_eq!(home_whites, (team_size * team_size).div_ceil(2));
+        }
+    }
+
+    // Two players a side, and odd numbers of boards, where team A has the extra white
+    #[test]
+    fn teams_meet_board_by_board() {
+        for team_size in [2, 3, 5] {
+            let mut tournament = scheveningen(team_size);
+            let table = scheveningen_table(team_size as usize).unwrap();
+            let mut games = BTreeSet::new();
+
+            for boards in &table {
+                let pairings = tournament.pair().unwrap();
+                let expected = boards
+                    .iter()
+                    .map(|&(a, b, color)| {
+                        Pairing::new(team_size + a as PlayerId, b as PlayerId, color, !color)
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(pairings, expected, "{team_size} a side");
+
+                for pairing in &pairings {
+                    games.insert((pairing.left(), pairing.right().unwrap()));
+                    tournament.submit_result(pairing, GameResult::Draw).unwrap();
+                }
+                tournament.close_round().unwrap();
+            }
+
+            assert_eq!(games.len() as u64, team_size * team_size);
+            assert!(tournament.pair().is_err());
+        }
+    }
+
+    #[test]
+    fn every_player_needs_a_team() {
+        let mut swiss = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        swiss.add_player(Player::new(1, "Player 1", Title::None, 2400));
+        assert!(swiss.assign_team(1, Team::A).is_err());
+
+        let mut tournament = scheveningen(2);
+        assert!(tournament.assign_team(5, Team::A).is_err());
+        tournament.add_player(Player::new(5, "Player 5", Title::None, 2000));
+        assert!(tournament.clone().pair().is_err());
+
+        // Three against two
+        tournament.assign_team(5, Team::A).unwrap();
+        assert!(tournament.clone().pair().is_err());
+
+        // Teams can change until the start
+        let mut tournament = scheveningen(2);
+        tournament.assign_team(1, Team::A).unwrap();
+        tournament.assign_team(3, Team::B).unwrap();
+        tournament.pair().unwrap();
+        assert_eq!(tournament.team_of(1), Some(Team::A));
+        assert!(tournament.assign_team(1, Team::B).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use itertools::Itertools as _;
+
+use crate::chess_types::Title;
+use crate::player::PlayerId;
+use crate::tiebreak::{Tiebreak, TiebreakValue};
+use crate::tournament::Tournament;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StandingsRow {
+    // Players sharing a place all get the same range, e.g. 3 to 5
+    pub place: u16,
+    pub last_place: u16,
+
+    pub player_id: PlayerId,
+    pub name: String,
+    pub title: Title,
+    pub rating: u16,
+
+    pub score: u8,
+    pub games_played: u8,
+    pub tiebreaks: Vec<TiebreakValue>,
+}
+
+impl StandingsRow {
+    #[inline]
+    #[must_use]
+    pub const fn is_shared(&self) -> bool {
+        self.place != self.last_place
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_string_csv_like(&self) -> String {
+        let place = if self.is_shared() {
+            format!("{}-{}", self.place, self.last_place)
+        } else {
+            self.place.to_string()
+        };
+
+        format!(
+            "{} {} {} {:?} {} {} {} {}",
+            place,
+            self.player_id,
+            self.name,
+            self.title,
+            self.rating,
+            self.score,
+            self.games_played,
+            self.tiebreaks.iter().join(" ")
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn csv_header() -> &'static str {
+        "place id name title rating score games_played tiebreaks..."
+    }
+}
+
+// Players ordered by score, then by each tiebreak in turn. The direct encounter is computed
+// among the players on the same score.
+#[must_use]
+pub fn standings(tournament: &Tournament, tiebreaks: &[Tiebreak]) -> Vec<StandingsRow> {
+    let score_groups = tournament.get_score_groups();
+
+    let rows = tournament
+        .get_players()
+        .values()
+        .map(|player| {
+            let id = player.info.id;
+            let tied = score_groups
+                .get(&player.score)
+                .map_or(&[][..], |x| x.as_slice());
+
+            let games_played = tournament
+                .games_of_player(id)
+                .filter(|(_, game)| game.is_played())
+                .count() as u8;
+
+            StandingsRow {
+                place: 0,
+                last_place: 0,
+                player_id: id,
+                name: player.info.name.clone(),
+                title: player.info.title,
+                rating: player.info.rating,
+                score: player.score,
+                games_played,
+                tiebreaks: tiebreaks
+                    .iter()
+                    .map(|x| x.compute(tournament, id, tied))
+                    .collect(),
+            }
+        })
+        .sorted_by(|a, b| (b.score, &b.tiebreaks, &a.name).cmp(&(a.score, &a.tiebreaks, &b.name)))
+        .collect::<Vec<_>>();
+
+    // Rows are only shared when every criterion is equal
+    let mut result = Vec::with_capacity(rows.len());
+    for (_, group) in &rows
+        .into_iter()
+        .chunk_by(|x| (x.score, x.tiebreaks.clone()))
+    {
+        let group = group.collect::<Vec<_>>();
+        let place = result.len() as u16 + 1;
+        let last_place = place + group.len() as u16 - 1;
+
+        result.extend(group.into_iter().map(|x| StandingsRow {
+            place,
+            last_place,
+            ..x
+        }));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::standings;
+    use crate::chess_types::{GameResult, Title};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::{Player, PlayerId};
+    use crate::tiebreak::Tiebreak;
+    use crate::tournament::Tournament;
+
+    // 1 beats 3 and 2 draws with 4, names go the other way round from ratings
+    fn tournament() -> Tournament {
+        let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        for (id, name) in (1..=4).zip(["Dana", "Carol", "Bob", "Alice"]) {
+            let rating = 2500 - 100 * id as u16;
+            tournament.add_player(Player::new(id, name, Title::None, rating));
+        }
+
+        for pairing in tournament.pair().unwrap() {
+            let result = if pairing.left() == 1 {
+                GameResult::LeftWins
+            } else {
+                GameResult::Draw
+            };
+            tournament.submit_result(&pairing, result).unwrap();
+        }
+        tournament.close_round().unwrap();
+
+        tournament
+    }
+
+    fn places(tournament: &Tournament, tiebreaks: &[Tiebreak]) -> Vec<(PlayerId, u16, u16)> {
+        standings(tournament, tiebreaks)
+            .into_iter()
+            .map(|x| (x.player_id, x.place, x.last_place))
+            .collect()
+    }
+
+    // Players level on everything share the place and are listed by name
+    #[test]
+    fn equal_players_share_places() {
+        let tournament = tournament();
+        assert_eq!(tournament.opponent_in_round(2, 1), Some(4));
+
+        let rows = standings(&tournament, &[]);
+        assert_eq!(
+            places(&tournament, &[]),
+            [(1, 1, 1), (4, 2, 3), (2, 2, 3), (3, 4, 4)]
+        );
+        assert!(rows[1].is_shared() && rows[2].is_shared());
+        assert!(!rows[0].is_shared());
+        assert!(rows.iter().all(|x| x.games_played == 1));
+        assert!(rows[1].as_string_csv_like().starts_with("2-3 4 Alice"));
+    }
+
+    // Tiebreaks go before names, the higher value first
+    #[test]
+    fn tiebreaks_split_shared_places() {
+        let tournament = tournament();
+        let tiebreaks = [Tiebreak::AverageRatingOfOpponents];
+
+        // Player 4 met the higher rated opponent
+        assert_eq!(
+            places(&tournament, &tiebreaks),
+            [(1, 1, 1), (4, 2, 2), (2, 3, 3), (3, 4, 4)]
+        );
+
+        let rows = standings(&tournament, &tiebreaks);
+        assert!(rows[1].tiebreaks > rows[2].tiebreaks);
+        assert!(rows.iter().all(|x| !x.is_shared()));
+    }
+}
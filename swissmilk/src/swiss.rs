@@ -0,0 +1,544 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+use itertools::Itertools as _;
+
+use crate::chess_types::{Color, ColorPreference, ColorPreferenceLevel};
+use crate::matching;
+use crate::pairing::Pairing;
+use crate::player::{Player, PlayerId};
+use crate::tournament::Tournament;
+use crate::trace::{BracketTrace, ColorRule, PairingTrace};
+
+// Nodes visited by the quick completion (C4) search before deferring to an exact matching
+const MAX_COMPLETION_NODES: usize = 2_000;
+
+pub(crate) fn pair_first_round(
+    tournament: &Tournament,
+    available: &BTreeSet<PlayerId>,
+    mut trace: Option<&mut PairingTrace>,
+) -> Vec<Pairing> {
+    let mut pairings = Vec::with_capacity(tournament.player_count() >> 1);
+    let initial_color = tournament.initial_color();
+
+    // There's more than one score group only when accelerated, an odd player out floats down
+    let mut players = Vec::with_capacity(tournament.player_count());
+    for (&score, ids) in tournament.get_pairing_score_groups().iter().rev() {
+        let downfloaters = players.len();
+        players.extend(
+            ids.iter()
+                .filter(|id| available.contains(id))
+                .filter_map(|id| tournament.get_player(*id))
+                .sorted_by_key(|player| player.pairing_number)
+                .map(|player| player.info.id),
+        );
+
+        // The top half meets the bottom half in order
+        let half = players.len() >> 1;
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.brackets.push(BracketTrace {
+                score,
+                residents: players[downfloaters..].to_vec(),
+                downfloaters: players[..downfloaters].to_vec(),
+                s1: players[..half].to_vec(),
+                s2: players[half..].to_vec(),
+                pairs: (0..half).map(|i| (players[i], players[i + half])).collect(),
+                floaters: players[half << 1..].to_vec(),
+                ..Default::default()
+            });
+        }
+
+        for i in 0..half {
+            // The top player of every odd board gets the initial colour
+            let color_left = if pairings.len() % 2 == 0 {
+                initial_color
+            } else {
+                !initial_color
+            };
+
+            pairings.push(Pairing::new(
+                players[i],
+                players[i + half],
+                color_left,
+                !color_left,
+            ));
+        }
+
+        players.drain(..half << 1);
+    }
+
+    if let Some(&id) = players.first() {
+        pairings.push(Pairing::bye(id));
+    }
+
+    if let Some(trace) = trace {
+        trace.set_boards(&pairings);
+        for board in trace.boards.iter_mut().filter(|x| !x.pairing.is_bye()) {
+            board.rule = Some(ColorRule::FirstRound);
+        }
+    }
+
+    pairings
+}
+
+pub(crate) struct Entrant<'a> {
+    pub(crate) player: &'a Player,
+    pub(crate) score: u8,
+    pub(crate) preference: ColorPreference,
+    pub(crate) is_topscorer: bool,
+}
+
+// Whether the colour preferences of two entrants let them meet, the systems' own C3
+pub(crate) type ColorCompatibility = fn(&SwissContext<'_>, &Entrant<'_>, &Entrant<'_>) -> bool;
+
+// Topscorers may meet in the last round despite the same absolute preference (C.04.3 C3)
+fn dutch_colors_compatible(context: &SwissContext<'_>, a: &Entrant<'_>, b: &Entrant<'_>) -> bool {
+    if a.preference.level() == ColorPreferenceLevel::Absolute
+        && b.preference.level() == ColorPreferenceLevel::Absolute
+        && a.preference.color() == b.preference.color()
+    {
+        return context.is_last_round && (a.is_topscorer || b.is_topscorer);
+    }
+
+    true
+}
+
+// Entrants are stored in ranking order (score, then pairing number), so an index doubles as a BSN
+pub(crate) struct SwissContext<'a> {
+    pub(crate) entrants: Vec<Entrant<'a>>,
+    pub(crate) brackets: Vec<Range<usize>>,
+    pub(crate) win_score: u8,
+    pub(crate) is_last_round: bool,
+    pub(crate) initial_color: Color,
+    colors_compatible: ColorCompatibility,
+}
+
+impl<'a> SwissContext<'a> {
+    #[inline]
+    pub(crate) fn new(tournament: &'a Tournament, available: &BTreeSet<PlayerId>) -> Self {
+        Self::new_with_color_compatibility(tournament, available, dutch_colors_compatible)
+    }
+
+    pub(crate) fn new_with_color_compatibility(
+        tournament: &'a Tournament,
+        available: &BTreeSet<PlayerId>,
+        colors_compatible: ColorCompatibility,
+    ) -> Self {
+        let (win_score, ..) = tournament.result_scores();
+        let rounds_played = tournament.current_round().saturating_sub(1);
+        let topscorer_threshold = (u16::from(win_score) * u16::from(rounds_played)) >> 1;
+
+        let mut entrants = Vec::with_capacity(tournament.player_count());
+        let mut brackets = Vec::new();
+
+        for (&score, ids) in tournament.get_pairing_score_groups().iter().rev() {
+            let start = entrants.len();
+
+            entrants.extend(
+                ids.iter()
+                    .filter(|id| available.contains(id))
+                    .filter_map(|id| tournament.get_player(*id))
+                    .sorted_by_key(|player| player.pairing_number)
+                    .map(|player| Entrant {
+                        player,
+                        score,
+                        preference: player.get_dutch_color_preference(),
+                        is_topscorer: u16::from(score) > topscorer_threshold,
+                    }),
+            );
+
+            if entrants.len() > start {
+                brackets.push(start..entrants.len());
+            }
+        }
+
+        Self {
+            entrants,
+            brackets,
+            win_score,
+            is_last_round: tournament.current_round() >= tournament.number_of_rounds(),
+            initial_color: tournament.initial_color(),
+            colors_compatible,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn ids(&self, players: &[usize]) -> Vec<PlayerId> {
+        players
+            .iter()
+            .map(|&x| self.entrants[x].player.info.id)
+            .collect()
+    }
+
+    // C1 and C3
+    pub(crate) fn compatible(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (&self.entrants[a], &self.entrants[b]);
+
+        !a.player.previous_opponents.contains(&b.player.info.id)
+            && (self.colors_compatible)(self, a, b)
+    }
+
+    // C2
+    pub(crate) fn can_receive_bye(&self, a: usize) -> bool {
+        !self.entrants[a].player.has_bye
+    }
+
+    // C4: can everyone in `players` be paired, leaving at most one bye-eligible player out?
+    pub(crate) fn can_complete(&self, players: &[usize]) -> bool {
+        let mut unpaired = players.to_vec();
+        let bye_available = unpaired.len() % 2 == 1;
+        let mut visited = 0;
+
+        // A depth-first search settles the usual, densely compatible case much faster
+        if self.complete(&mut unpaired, bye_available, &mut visited) {
+            return true;
+        }
+
+        visited > MAX_COMPLETION_NODES && self.find_completion(players).is_some()
+    }
+
+    fn complete(
+        &self,
+        unpaired: &mut Vec<usize>,
+        bye_available: bool,
+        visited: &mut usize,
+    ) -> bool {
+        let Some(current) = unpaired.pop() else {
+            return true;
+        };
+
+        *visited += 1;
+        if *visited > MAX_COMPLETION_NODES {
+            unpaired.push(current);
+            return false;
+        }
+
+        for i in (0..unpaired.len()).rev() {
+            if !self.compatible(current, unpaired[i]) {
+                continue;
+            }
+
+            let opponent = unpaired.swap_remove(i);
+            if self.complete(unpaired, bye_available, visited) {
+                return true;
+            }
+
+            unpaired.push(opponent);
+            let last = unpaired.len() - 1;
+            unpaired.swap(i, last);
+        }
+
+        if bye_available && self.can_receive_bye(current) && self.complete(unpaired, false, visited)
+        {
+            return true;
+        }
+
+        unpaired.push(current);
+        false
+    }
+
+    // Exact completion through a maximum cardinality matching. An odd player out is matched
+    // with an extra bye vertex, which prefers the lowest ranked eligible player.
+    pub(crate) fn find_completion(&self, players: &[usize]) -> Option<Vec<(usize, Option<usize>)>> {
+        let bye = players.len();
+        let mut edges = Vec::new();
+
+        for (i, &a) in players.iter().enumerate() {
+            for (j, &b) in players.iter().enumerate().skip(i + 1) {
+                if self.compatible(a, b) {
+                    edges.push((i, j, 1));
+                }
+            }
+
+            if players.len() % 2 == 1 && self.can_receive_bye(a) {
+                edges.push((i, bye, 1 + i as matching::Weight));
+            }
+        }
+
+        let mates = matching::maximum_weight_matching(bye + players.len() % 2, &edges, true);
+        if mates.iter().any(Option::is_none) {
+            return None;
+        }
+
+        let completion = mates
+            .iter()
+            .take(players.len())
+            .enumerate()
+            .filter_map(|(i, mate)| match *mate {
+                Some(j) if j == bye => Some((players[i], None)),
+                Some(j) if i < j => Some((players[i], Some(players[j]))),
+                _ => None,
+            })
+            .collect();
+
+        Some(completion)
+    }
+
+    // Colour allocation rules (C.04.3 E.1-E.5, C.04.1), `high` being the higher ranked player
+    #[inline]
+    pub(crate) fn allocate_colors(&self, high: usize, low: usize) -> (Color, Color) {
+        let (high_color, low_color, _) = self.allocate_colors_with_rule(high, low);
+        (high_color, low_color)
+    }
+
+    pub(crate) fn allocate_colors_with_rule(
+        &self,
+        high: usize,
+        low: usize,
+    ) -> (Color, Color, ColorRule) {
+        let (high_player, low_player) = (&self.entrants[high], &self.entrants[low]);
+        let (high_pref, low_pref) = (high_player.preference, low_player.preference);
+
+        match (high_pref.color(), low_pref.color()) {
+            (Color::None, Color::None) => {}
+            (Color::None, color) => return (!color, color, ColorRule::BothPreferences),
+            (color, other) if color != other => {
+                return (color, !color, ColorRule::BothPreferences);
+            }
+            _ => {}
+        }
+
+        if high_pref.level() != low_pref.level() || high_pref.width() != low_pref.width() {
+            let (high_color, low_color) =
+                if (high_pref.level(), high_pref.width()) > (low_pref.level(), low_pref.width()) {
+                    (high_pref.color(), !high_pref.color())
+                } else {
+                    (!low_pref.color(), low_pref.color())
+                };
+
+            return (high_color, low_color, ColorRule::StrongerPreference);
+        }
+
+        let histories = high_player
+            .player
+            .color_history
+            .iter()
+            .rev()
+            .zip(low_player.player.color_history.iter().rev());
+
+        for (&high_color, &low_color) in histories {
+            if high_color != low_color && high_color != Color::None && low_color != Color::None {
+                return (!high_color, !low_color, ColorRule::ColorHistory);
+            }
+        }
+
+        if high_pref.color() != Color::None {
+            return (
+                high_pref.color(),
+                !high_pref.color(),
+                ColorRule::HigherRankedPreference,
+            );
+        }
+
+        if high_player.player.pairing_number % 2 == 1 {
+            (
+                self.initial_color,
+                !self.initial_color,
+                ColorRule::PairingNumber,
+            )
+        } else {
+            (
+                !self.initial_color,
+                self.initial_color,
+                ColorRule::PairingNumber,
+            )
+        }
+    }
+
+    // Boards of the trace, `rule` gets the players on the left and on the right of a board
+    pub(crate) fn trace_boards(
+        &self,
+        trace: &mut PairingTrace,
+        pairings: &[Pairing],
+        rule: impl Fn(usize, usize) -> ColorRule,
+    ) {
+        let index = self
+            .entrants
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.player.info.id, i))
+            .collect::<HashMap<_, _>>();
+
+        trace.set_boards(pairings);
+        for board in &mut trace.boards {
+            let Pairing::Game { left, right, .. } = board.pairing else {
+                continue;
+            };
+
+            board.rule = Some(rule(index[&left], index[&right]));
+        }
+    }
+
+    // Boards are ordered by the higher score, then the sum of scores, then the higher rank
+    pub(crate) fn to_pairings(
+        &self,
+        pairs: Vec<(usize, usize)>,
+        bye: Option<usize>,
+        colors: impl Fn(usize, usize) -> (Color, Color),
+    ) -> Vec<Pairing> {
+        let mut pairings = pairs
+            .into_iter()
+            .sorted_by_key(|&(high, low)| {
+                let (high_score, low_score) = (self.entrants[high].score, self.entrants[low].score);
+                (
+                    Reverse(high_score.max(low_score)),
+                    Reverse(u16::from(high_score) + u16::from(low_score)),
+                    high.min(low),
+                )
+            })
+            .map(|(high, low)| {
+                let (color_high, color_low) = colors(high, low);
+                Pairing::new(
+                    self.entrants[high].player.info.id,
+                    self.entrants[low].player.info.id,
+                    color_high,
+                    color_low,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(bye) = bye {
+            pairings.push(Pairing::bye(self.entrants[bye].player.info.id));
+        }
+
+        pairings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess_types::{ByeValue, Color, GameResult, Title};
+    use crate::pairing::Pairing;
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::Player;
+    use crate::tournament::Tournament;
+
+    // Players 2 and 3 sit out round 1, then meet with no colour history in round 2
+    fn second_round_colors(initial_color: Color) -> (Color, Color) {
+        let mut tournament = Tournament::new(5, PairingSystemType::ClassicSwiss);
+        for id in 1..=6 {
+            let rating = 2500 - 100 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        tournament.set_initial_color(initial_color);
+        tournament.start().unwrap();
+        tournament.request_bye(2, 1, ByeValue::Loss).unwrap();
+        tournament.request_bye(3, 1, ByeValue::Loss).unwrap();
+
+        for pairing in tournament.pair().unwrap() {
+            tournament
+                .submit_result(&pairing, GameResult::Draw)
+                .unwrap();
+        }
+        tournament.close_round().unwrap();
+
+        let pairing = tournament
+            .pair()
+            .unwrap()
+            .into_iter()
+            .find(|x| x.left() == 2 || x.right() == Some(2))
+            .unwrap();
+
+        match pairing {
+            Pairing::Game {
+                left: 2,
+                right: 3,
+                color_left,
+                color_right,
+            } => (color_left, color_right),
+            Pairing::Game {
+                left: 3,
+                right: 2,
+                color_left,
+                color_right,
+            } => (color_right, color_left),
+            _ => panic!("Players 2 and 3 weren't paired together: {pairing:?}"),
+        }
+    }
+
+    // E.5 goes by the pairing number, not the rank among the players being paired
+    #[test]
+    fn pairing_number_decides_colors_of_players_sitting_out() {
+        assert_eq!(
+            second_round_colors(Color::White),
+            (Color::Black, Color::White)
+        );
+        assert_eq!(
+            second_round_colors(Color::Black),
+            (Color::White, Color::Black)
+        );
+    }
+
+    // Tracing doesn't change the pairings, and the brackets account for every board
+    #[test]
+    fn every_swiss_system_traces_brackets_and_colors() {
+        for system in [
+            PairingSystemType::ClassicSwiss,
+            PairingSystemType::DubovSwiss,
+            PairingSystemType::BursteinSwiss,
+            PairingSystemType::Lim,
+        ] {
+            let mut tournament = Tournament::new(6, system);
+            for id in 1..=11 {
+                let rating = 2500 - 50 * id as u16;
+                tournament.add_player(Player::new(
+                    id,
+                    &format!("Player {id}"),
+                    Title::None,
+                    rating,
+                ));
+            }
+
+            for round in 1..=4 {
+                let untraced = tournament.clone().pair().unwrap();
+                let (pairings, trace) = tournament.pair_with_trace().unwrap();
+                assert_eq!(pairings, untraced, "{system:?} round {round}");
+                assert_eq!(trace.round, round);
+                assert!(!trace.brackets.is_empty());
+
+                let mut games = pairings
+                    .iter()
+                    .filter_map(|x| Some((x.left().min(x.right()?), x.left().max(x.right()?))))
+                    .collect::<Vec<_>>();
+                let mut traced = trace
+                    .brackets
+                    .iter()
+                    .flat_map(|x| x.pairs.iter().map(|&(a, b)| (a.min(b), a.max(b))))
+                    .collect::<Vec<_>>();
+                games.sort_unstable();
+                traced.sort_unstable();
+                assert_eq!(games, traced, "{system:?} round {round}");
+
+                let byes = pairings
+                    .iter()
+                    .filter(|x| x.is_bye())
+                    .map(Pairing::left)
+                    .collect::<Vec<_>>();
+                assert_eq!(trace.brackets.last().unwrap().floaters, byes);
+                assert!(
+                    trace
+                        .boards
+                        .iter()
+                        .all(|x| x.pairing.is_bye() == x.rule.is_none())
+                );
+
+                for pairing in pairings.iter().filter(|x| !x.is_bye()) {
+                    let result = match (pairing.left() + pairing.right().unwrap()) % 3 {
+                        0 => GameResult::LeftWins,
+                        1 => GameResult::Draw,
+                        _ => GameResult::RightWins,
+                    };
+                    tournament.submit_result(pairing, result).unwrap();
+                }
+                tournament.close_round().unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use itertools::Itertools as _;
+use smallvec::SmallVec;
+
+use crate::chess_types::Color;
+use crate::pairing_system::PairingSystemType;
+use crate::player::PlayerId;
+use crate::tournament::Tournament;
+
+// Score based values are in the units of the tournament's ResultScoreConfig, rating based ones
+// (ARO, TPR) in rating points
+pub type TiebreakValue = i32;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Tiebreak {
+    Buchholz,
+    BuchholzCut1,
+    BuchholzCut2,
+    MedianBuchholz,
+    SonnebornBerger,
+    ProgressiveScore,
+    DirectEncounter,
+    // Games won over the board
+    Wins,
+    // Rounds scored as a win, byes and forfeits included
+    Won,
+    WinsWithBlack,
+    GamesWithBlack,
+    AverageRatingOfOpponents,
+    TournamentPerformanceRating,
+    Koya,
+    // Sonneborn-Berger for round-robins: final scores of the opponents, played games only
+    Berger,
+}
+
+// What a player did in a finished round
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Played {
+        opponent: PlayerId,
+        color: Color,
+        points: u8,
+    },
+    // Byes, forfeits and rounds the player wasn't in
+    Unplayed {
+        points: u8,
+    },
+}
+
+// FIDE B.02 8.1.1, rating difference by percentage score
+const RATING_DIFFERENCES: [i32; 51] = [
+    0, 7, 14, 21, 29, 36, 43, 50, 57, 65, 72, 80, 87, 95, 102, 110, 117, 125, 133, 141, 149, 158,
+    166, 175, 184, 193, 202, 211, 220, 230, 240, 251, 262, 273, 284, 296, 309, 322, 336, 351, 366,
+    383, 401, 422, 444, 470, 501, 538, 589, 677, 800,
+];
+
+impl Tiebreak {
+    // FIDE C.07 recommendations for each kind of event
+    #[must_use]
+    pub const fn default_order(pairing_system: PairingSystemType) -> &'static [Self] {
+        match pairing_system {
+            PairingSystemType::BergerTable | PairingSystemType::Scheveningen => &[
+                Self::DirectEncounter,
+                Self::Wins,
+                Self::SonnebornBerger,
+                Self::Koya,
+            ],
+            _ => &[
+                Self::BuchholzCut1,
+                Self::Buchholz,
+                Self::DirectEncounter,
+                Self::AverageRatingOfOpponents,
+            ],
+        }
+    }
+
+    // Players tied with the player are needed by the direct encounter
+    #[must_use]
+    pub fn compute(
+        self,
+        tournament: &Tournament,
+        player_id: PlayerId,
+        tied: &[PlayerId],
+    ) -> TiebreakValue {
+        let Some(player) = tournament.get_player(player_id) else {
+            return 0;
+        };
+
+        let (win, _, _) = tournament.result_scores();
+        let score = TiebreakValue::from(player.score);
+        let entries = Self::entries(tournament, player_id);
+
+        match self {
+            Self::Buchholz => Self::buchholz(tournament, &entries, score, 0, 0),
+            Self::BuchholzCut1 => Self::buchholz(tournament, &entries, score, 1, 0),
+            Self::BuchholzCut2 => Self::buchholz(tournament, &entries, score, 2, 0),
+            Self::MedianBuchholz => Self::buchholz(tournament, &entries, score, 1, 1),
+            Self::SonnebornBerger => entries
+                .iter()
+                .map(|x| match *x {
+                    Entry::Played {
+                        opponent, points, ..
+                    } => TiebreakValue::from(points) * Self::adjusted_score(tournament, opponent),
+                    Entry::Unplayed { points } => TiebreakValue::from(points) * score,
+                })
+                .sum(),
+            Self::ProgressiveScore => entries
+                .iter()
+                .scan(0, |total, x| {
+                    *total += TiebreakValue::from(Self::points(*x));
+                    Some(*total)
+                })
+                .sum(),
+            Self::DirectEncounter => Self::direct_encounter(tournament, player_id, tied),
+            Self::Wins => entries
+                .iter()
+                .filter(|x| matches!(x, Entry::Played { points, .. } if *points == win))
+                .count() as TiebreakValue,
+            Self::Won => entries.iter().filter(|x| Self::points(**x) == win).count() as TiebreakValue,
+            Self::WinsWithBlack => entries
+                .iter()
+                .filter(|x| {
+                    matches!(x, Entry::Played { color: Color::Black, points, .. } if *points == win)
+                })
+                .count() as TiebreakValue,
+            Self::GamesWithBlack => entries
+                .iter()
+                .filter(|x| matches!(x, Entry::Played { color: Color::Black, .. }))
+                .count() as TiebreakValue,
+            Self::AverageRatingOfOpponents => Self::average_rating_of_opponents(tournament, &entries),
+            Self::TournamentPerformanceRating => Self::performance_rating(tournament, &entries),
+            Self::Koya => {
+                let half = u16::from(tournament.number_of_rounds()) * u16::from(win);
+
+                entries
+                    .iter()
+                    .filter_map(|x| match *x {
+                        Entry::Played {
+                            opponent, points, ..
+                        } => Some((tournament.get_player(opponent)?, points)),
+                        Entry::Unplayed { .. } => None,
+                    })
+                    .filter(|(opponent, _)| 2 * u16::from(opponent.score) >= half)
+                    .map(|(_, points)| TiebreakValue::from(points))
+                    .sum()
+            }
+            Self::Berger => entries
+                .iter()
+                .filter_map(|x| match *x {
+                    Entry::Played {
+                        opponent, points, ..
+                    } => Some(
+                        TiebreakValue::from(points)
+                            * TiebreakValue::from(tournament.get_player(opponent)?.score),
+                    ),
+                    Entry::Unplayed { .. } => None,
+                })
+                .sum(),
+        }
+    }
+
+    fn entries(tournament: &Tournament, player_id: PlayerId) -> SmallVec<[Entry; 16]> {
+        let scores = tournament.result_scores();
+
+        tournament
+            .finished_rounds()
+            .map(|round| {
+                let Some(game) = round.game_of(player_id) else {
+                    return Entry::Unplayed { points: 0 };
+                };
+
+                let points = game.points_of(player_id, scores).unwrap_or(0);
+                match (game.opponent_of(player_id), game.color_of(player_id)) {
+                    (Some(opponent), Some(color)) => Entry::Played {
+                        opponent,
+                        color,
+                        points,
+                    },
+                    _ => Entry::Unplayed { points },
+                }
+            })
+            .collect()
+    }
+
+    #[inline]
+    const fn points(entry: Entry) -> u8 {
+        match entry {
+            Entry::Played { points, .. } | Entry::Unplayed { points } => points,
+        }
+    }
+
+    // Unplayed rounds of an opponent count as draws (C.07 2023, 16.3)
+    fn adjusted_score(tournament: &Tournament, player_id: PlayerId) -> TiebreakValue {
+        let (_, draw, _) = tournament.result_scores();
+
+        Self::entries(tournament, player_id)
+            .into_iter()
+            .map(|x| match x {
+                Entry::Played { points, .. } => TiebreakValue::from(points),
+                Entry::Unplayed { .. } => TiebreakValue::from(draw),
+            })
+            .sum()
+    }
+
+    // The player's own unplayed rounds face a dummy opponent on the player's score, and those
+    // are the first to be cut (C.07 2023, 16.4 and 16.5)
+    fn buchholz(
+        tournament: &Tournament,
+        entries: &[Entry],
+        score: TiebreakValue,
+        cut_lowest: usize,
+        cut_highest: usize,
+    ) -> TiebreakValue {
+        let contributions = entries
+            .iter()
+            .map(|x| match *x {
+                Entry::Played { opponent, .. } => {
+                    (true, Self::adjusted_score(tournament, opponent))
+                }
+                Entry::Unplayed { .. } => (false, score),
+            })
+            .sorted_unstable()
+            .collect::<SmallVec<[(bool, TiebreakValue); 16]>>();
+
+        // Played games sort after the unplayed ones, so the highest is the last
+        let end = contributions.len().saturating_sub(cut_highest);
+        contributions
+            .get(cut_lowest..end)
+            .map_or(0, |x| x.iter().map(|x| x.1).sum())
+    }
+
+    // Points scored against the other tied players, only when they all met each other
+    fn direct_encounter(
+        tournament: &Tournament,
+        player_id: PlayerId,
+        tied: &[PlayerId],
+    ) -> TiebreakValue {
+        let others = tied
+            .iter()
+            .copied()
+            .filter(|&x| x != player_id)
+            .collect::<SmallVec<[PlayerId; 8]>>();
+        let scores = tournament.result_scores();
+
+        let have_met = |a: PlayerId, b: PlayerId| {
+            tournament
+                .games_of_player(a)
+                .any(|(_, game)| game.opponent_of(a) == Some(b) && game.is_played())
+        };
+
+        let everyone_met = tied
+            .iter()
+            .tuple_combinations()
+            .all(|(&a, &b)| have_met(a, b));
+
+        if others.is_empty() || !everyone_met {
+            return 0;
+        }
+
+        tournament
+            .games_of_player(player_id)
+            .filter(|(_, game)| {
+                game.opponent_of(player_id)
+                    .is_some_and(|x| others.contains(&x))
+            })
+            .filter_map(|(_, game)| game.points_of(player_id, scores))
+            .map(TiebreakValue::from)
+            .sum()
+    }
+
+    fn average_rating_of_opponents(tournament: &Tournament, entries: &[Entry]) -> TiebreakValue {
+        let ratings = entries
+            .iter()
+            .filter_map(|x| match *x {
+                Entry::Played { opponent, .. } => tournament.get_player(opponent),
+                Entry::Unplayed { .. } => None,
+            })
+            .map(|x| TiebreakValue::from(x.info.rating))
+            .collect::<SmallVec<[TiebreakValue; 16]>>();
+
+        if ratings.is_empty() {
+            return 0;
+        }
+
+        let count = ratings.len() as TiebreakValue;
+        (ratings.iter().sum::<TiebreakValue>() + (count >> 1)) / count
+    }
+
+    // ARO plus the rating difference of the percentage scored in played games
+    fn performance_rating(tournament: &Tournament, entries: &[Entry]) -> TiebreakValue {
+        let (win, _, _) = tournament.result_scores();
+        let (points, games) = entries.iter().fold((0, 0), |(points, games), x| match *x {
+            Entry::Played { points: p, .. } => (points + TiebreakValue::from(p), games + 1),
+            Entry::Unplayed { .. } => (points, games),
+        });
+
+        if games == 0 || win == 0 {
+            return 0;
+        }
+
+        // Percentage rounded to the nearest whole number
+        let maximum = games * TiebreakValue::from(win);
+        let percentage = ((200 * points + maximum) / (2 * maximum)).clamp(0, 100);
+        let difference = if percentage >= 50 {
+            RATING_DIFFERENCES[(percentage - 50) as usize]
+        } else {
+            -RATING_DIFFERENCES[(50 - percentage) as usize]
+        };
+
+        Self::average_rating_of_opponents(tournament, entries) + difference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tiebreak, TiebreakValue};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::PlayerId;
+    use crate::tournament::Tournament;
+    use crate::trf;
+
+    // Six players, three rounds, 1-½-0 scoring kept in half points. Player 3 wins round 2 by
+    // forfeit against player 5, player 4 takes a half-point bye and player 6 the allocated bye.
+    //
+    //      rating  round 1  round 2  round 3  score
+    //   1  2400    4 w 1    2 b 1    6 w =    2.5
+    //   2  2300    5 w =    1 w 0    3 w 1    1.5
+    //   3  2200    6 w 0    5 w +    2 b 0    1
+    //   4  2100    1 b 0    bye ½    5 w 0    0.5
+    //   5  2000    2 b =    3 b -    4 b 1    1.5
+    //   6  1900    3 b 1    bye 1    1 b =    2.5
+    fn tournament() -> Tournament {
+        trf::replay(
+            &[2400, 2300, 2200, 2100, 2000, 1900],
+            &[
+                &[(4, 'w', '1'), (2, 'b', '1'), (6, 'w', '=')],
+                &[(5, 'w', '='), (1, 'w', '0'), (3, 'w', '1')],
+                &[(6, 'w', '0'), (5, 'w', '+'), (2, 'b', '0')],
+                &[(1, 'b', '0'), (0, '-', 'H'), (5, 'w', '0')],
+                &[(2, 'b', '='), (3, 'b', '-'), (4, 'b', '1')],
+                &[(3, 'b', '1'), (0, '-', 'U'), (1, 'b', '=')],
+            ],
+            3,
+            PairingSystemType::ClassicSwiss,
+        )
+    }
+
+    fn values(tiebreak: Tiebreak) -> Vec<TiebreakValue> {
+        let tournament = tournament();
+        (1..=6)
+            .map(|id| tiebreak.compute(&tournament, id, &[]))
+            .collect()
+    }
+
+    #[test]
+    fn fixture_scores() {
+        let tournament = tournament();
+        let scores = (1..=6)
+            .map(|id| tournament.get_player(id).unwrap().score)
+            .collect::<Vec<_>>();
+        assert_eq!(scores, [5, 3, 2, 1, 3, 5]);
+    }
+
+    // Opponents' scores with their unplayed rounds as draws: 5, 3, 1, 1, 4, 4. The player's own
+    // unplayed round counts the player's score, and is cut first.
+    #[test]
+    fn buchholz_and_cuts() {
+        assert_eq!(values(Tiebreak::Buchholz), [8, 10, 9, 10, 7, 11]);
+        assert_eq!(values(Tiebreak::BuchholzCut1), [7, 9, 7, 9, 4, 6]);
+        assert_eq!(values(Tiebreak::BuchholzCut2), [4, 5, 4, 5, 3, 5]);
+        assert_eq!(values(Tiebreak::MedianBuchholz), [3, 4, 3, 4, 1, 1]);
+    }
+
+    #[test]
+    fn sonneborn_berger() {
+        assert_eq!(values(Tiebreak::SonnebornBerger), [12, 6, 4, 1, 5, 17]);
+        // Played games only, the opponents' real scores
+        assert_eq!(values(Tiebreak::Berger), [13, 7, 0, 0, 5, 9]);
+    }
+
+    // 50 % of the maximum is 3 half points: players 1, 2, 5 and 6
+    #[test]
+    fn koya() {
+        assert_eq!(values(Tiebreak::Koya), [3, 1, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn wins_and_games_with_black() {
+        assert_eq!(values(Tiebreak::Wins), [2, 1, 0, 0, 1, 1]);
+        assert_eq!(values(Tiebreak::Won), [2, 1, 1, 0, 1, 2]);
+        assert_eq!(values(Tiebreak::WinsWithBlack), [1, 0, 0, 0, 1, 1]);
+        assert_eq!(values(Tiebreak::GamesWithBlack), [1, 0, 1, 1, 2, 2]);
+        assert_eq!(values(Tiebreak::ProgressiveScore), [11, 5, 4, 2, 5, 11]);
+    }
+
+    // Forfeits and byes have no opponent rating and don't count as games
+    #[test]
+    fn rating_based() {
+        assert_eq!(
+            values(Tiebreak::AverageRatingOfOpponents),
+            [2100, 2200, 2100, 2200, 2200, 2300]
+        );
+
+        // 5/6 = 83 % gives +273, 3/6 = 50 % nothing, 3/4 = 75 % +193, 0 % -800
+        assert_eq!(
+            values(Tiebreak::TournamentPerformanceRating),
+            [2373, 2200, 1300, 1400, 2393, 2493]
+        );
+    }
+
+    #[test]
+    fn direct_encounter() {
+        let tournament = tournament();
+        let value = |id: PlayerId, tied: &[PlayerId]| {
+            Tiebreak::DirectEncounter.compute(&tournament, id, tied)
+        };
+
+        // 1 and 6 drew, so did 2 and 5
+        assert_eq!(value(1, &[1, 6]), 1);
+        assert_eq!(value(6, &[1, 6]), 1);
+        assert_eq!(value(2, &[2, 5]), 1);
+        // 3 and 4 never met, the encounter doesn't count
+        assert_eq!(value(3, &[3, 4]), 0);
+    }
+}
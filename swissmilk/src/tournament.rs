@@ -1,20 +1,49 @@
-use std::collections::{BTreeMap, btree_map};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, btree_map};
 use std::error::Error;
 use std::fmt::Write as _;
+use std::time::SystemTime;
 
 use itertools::Itertools as _;
 use smallvec::{SmallVec, smallvec};
 
+use crate::acceleration::Acceleration;
 use crate::berger_table::BergerTablePairingSystem;
-use crate::chess_types::ResultScoreConfig;
+use crate::burstein::BursteinPairingSystem;
+use crate::chess_types::{ByeValue, Color, Float, GameResult, ResultScoreConfig, Team};
+use crate::dubov::DubovPairingSystem;
 use crate::dutch::DutchPairingSystem;
+use crate::lim::LimPairingSystem;
 use crate::pairing::Pairing;
-use crate::pairing_system::{PairingSystemType, PairngSystem as _};
+use crate::pairing_system::{PairingSystemType, PairngSystem};
 use crate::player::{Player, PlayerId};
+use crate::random::Rng;
+use crate::round::{Game, Round};
+use crate::scheveningen::ScheveningenPairingSystem;
+use crate::standings::{self, StandingsRow};
+use crate::tiebreak::{Tiebreak, TiebreakValue};
+use crate::trace::PairingTrace;
+use crate::varma::{self, Affiliation};
 
-#[derive(Debug, Default, PartialEq)]
+// Event details as published in the TRF header, dates are YYYY/MM/DD
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TournamentInfo {
+    pub name: String,
+    pub city: String,
+    pub federation: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub kind: String,
+    pub chief_arbiter: String,
+    pub deputy_arbiters: Vec<String>,
+    pub time_control: String,
+    pub round_dates: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Tournament {
     pairing_system: PairingSystemType,
+    info: TournamentInfo,
 
     // Optimal number of rounds ~= log_2(number of players) + k
     number_of_rounds: u8,
@@ -22,17 +51,41 @@ pub struct Tournament {
 
     players: BTreeMap<PlayerId, Player>,
 
-    // Player, round withdrawned
+    // Player, first round they no longer play
     withdrawn_players: SmallVec<[(PlayerId, u8); 2]>,
-    past_round_pairings: SmallVec<[Pairing; 13]>,
+    // Player, round they join
+    late_entries: SmallVec<[(PlayerId, u8); 2]>,
+    // Every paired round, the last one may still be in progress
+    rounds: Vec<Round>,
 
     result_scores: ResultScoreConfig,
+    // Colour of the top seed in the first round, None means white
+    initial_color: Color,
+    // Empty means the recommended order for the pairing system
+    tiebreaks: Vec<Tiebreak>,
+
+    // Round-robins and Scheveningens only, 0 means a single cycle
+    cycles: u8,
+    // Plays the last two rounds of every cycle but the last in reverse order, so no one gets the
+    // same colour three times in a row across cycles
+    swap_last_rounds: bool,
+    // Pairing numbers were drawn by lot, so starting keeps them
+    lots_drawn: bool,
+    acceleration: Acceleration,
+    // What the pairing-allocated bye scores
+    bye_value: ByeValue,
+    // Round, player, what the bye scores
+    requested_byes: BTreeMap<(u8, PlayerId), ByeValue>,
+    max_requested_byes: Option<u8>,
+    // Scheveningens only, the side of every player
+    teams: BTreeMap<PlayerId, Team>,
 }
 
 pub type ScoreGroups = BTreeMap<u8, SmallVec<[PlayerId; 24]>>;
 
 impl Tournament {
     #[inline]
+    #[must_use]
     pub fn new(number_of_rounds: u8, pairing_system: PairingSystemType) -> Self {
         Self {
             pairing_system,
@@ -43,6 +96,7 @@ impl Tournament {
     }
 
     #[inline]
+    #[must_use]
     pub fn new_with_result_scores(
         number_of_rounds: u8,
         pairing_system: PairingSystemType,
@@ -57,6 +111,7 @@ impl Tournament {
     }
 
     #[inline]
+    #[must_use]
     pub fn new_with_players(
         number_of_rounds: u8,
         pairing_system: PairingSystemType,
@@ -84,6 +139,7 @@ impl Tournament {
     }
 
     #[inline]
+    #[must_use]
     pub fn get_player(&self, player_id: PlayerId) -> Option<&Player> {
         self.players.get(&player_id)
     }
@@ -94,24 +150,724 @@ impl Tournament {
     }
 
     #[inline]
+    #[must_use]
+    pub const fn number_of_rounds(&self) -> u8 {
+        self.number_of_rounds
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn result_scores(&self) -> ResultScoreConfig {
+        self.result_scores
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn get_info(&self) -> &TournamentInfo {
+        &self.info
+    }
+
+    #[inline]
+    pub const fn get_info_mut(&mut self) -> &mut TournamentInfo {
+        &mut self.info
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn initial_color(&self) -> Color {
+        match self.initial_color {
+            Color::None => Color::White,
+            color => color,
+        }
+    }
+
+    #[inline]
+    pub const fn set_initial_color(&mut self, color: Color) {
+        self.initial_color = color;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn cycles(&self) -> u8 {
+        if self.cycles == 0 { 1 } else { self.cycles }
+    }
+
+    #[inline]
+    pub const fn set_cycles(&mut self, cycles: u8) {
+        self.cycles = cycles;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn swap_last_rounds(&self) -> bool {
+        self.swap_last_rounds
+    }
+
+    #[inline]
+    pub const fn set_swap_last_rounds(&mut self, swap_last_rounds: bool) {
+        self.swap_last_rounds = swap_last_rounds;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn acceleration(&self) -> Acceleration {
+        self.acceleration
+    }
+
+    #[inline]
+    pub const fn set_acceleration(&mut self, acceleration: Acceleration) {
+        self.acceleration = acceleration;
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn bye_value(&self) -> ByeValue {
+        self.bye_value
+    }
+
+    #[inline]
+    pub const fn set_bye_value(&mut self, bye_value: ByeValue) {
+        self.bye_value = bye_value;
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_tiebreaks(&self) -> &[Tiebreak] {
+        if self.tiebreaks.is_empty() {
+            return Tiebreak::default_order(self.pairing_system);
+        }
+
+        &self.tiebreaks
+    }
+
+    #[inline]
+    pub fn set_tiebreaks(&mut self, tiebreaks: Vec<Tiebreak>) {
+        self.tiebreaks = tiebreaks;
+    }
+
+    // Values of the configured tiebreaks, in order. `tied` are the players on the same score
+    #[must_use]
+    pub fn compute_tiebreaks(&self, player_id: PlayerId, tied: &[PlayerId]) -> Vec<TiebreakValue> {
+        self.get_tiebreaks()
+            .iter()
+            .map(|x| x.compute(self, player_id, tied))
+            .collect()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn standings(&self) -> Vec<StandingsRow> {
+        standings::standings(self, self.get_tiebreaks())
+    }
+
+    #[inline]
+    #[must_use]
     pub const fn get_pairing_system_type(&self) -> PairingSystemType {
         self.pairing_system
     }
 
-    #[inline]
+    /// Pairs the next round and records it. Players who aren't available for the round sit it
+    /// out, see `is_available`.
+    ///
+    /// # Errors
+    /// When there is no pairing system, the round can't be paired or a game is adjourned.
     pub fn pair(&mut self) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        let pairings = self.generate_pairings()?;
+        self.record_round(&pairings);
+        Ok(pairings)
+    }
+
+    /// Pairs like `pair`, along with how the pairing system got there
+    ///
+    /// # Errors
+    /// Like `pair`.
+    pub fn pair_with_trace(&mut self) -> Result<(Vec<Pairing>, PairingTrace), Box<dyn Error>> {
+        let mut trace = PairingTrace::default();
+        let pairings = self.generate_pairings_with(Some(&mut trace))?;
+        self.record_round(&pairings);
+        Ok((pairings, trace))
+    }
+
+    fn record_round(&mut self, pairings: &[Pairing]) {
+        // Pairing the same round again replaces the previous attempt
+        if self.rounds.last().is_some_and(|x| !x.is_finished()) {
+            self.rounds.pop();
+        }
+
+        let number = self.current_round;
+        let mut round = Round::new(number, pairings.to_vec(), self.bye_value);
+
+        // Fixed tables keep withdrawn players in place, their opponents win by forfeit
+        for game in &mut round.games {
+            let left = self.is_withdrawn_in(game.pairing.left(), number);
+            let right = game
+                .pairing
+                .right()
+                .map(|x| self.is_withdrawn_in(x, number));
+
+            game.result = match (left, right) {
+                (true, None) => Some(GameResult::ZeroPointBye),
+                (true, Some(true)) => Some(GameResult::DoubleForfeit),
+                (true, Some(false)) => Some(GameResult::RightWinsByForfeit),
+                (false, Some(true)) => Some(GameResult::LeftWinsByForfeit),
+                (false, _) => game.result,
+            };
+        }
+
+        for (id, value) in self.requested_byes(number).collect::<Vec<_>>() {
+            let board = round.games.len() as u16 + 1;
+            round
+                .games
+                .push(Game::requested_bye(board, id, value.result()));
+        }
+
+        self.rounds.push(round);
+    }
+
+    // Pairs the next round without recording it
+    #[inline]
+    pub(crate) fn generate_pairings(&mut self) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        self.generate_pairings_with(None)
+    }
+
+    fn generate_pairings_with(
+        &mut self,
+        mut trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        if self.pairing_system == PairingSystemType::None {
+            return Err("The tournament has no pairing system".into());
+        }
+
+        // Pairing again would throw away the adjourned games of the current round
+        if let Some(board) = self
+            .rounds
+            .last()
+            .filter(|x| !x.is_finished())
+            .and_then(|x| x.adjourned_boards().next())
+        {
+            return Err(format!("The game on board {board} is adjourned").into());
+        }
+
         if !self.has_started() {
-            self.start();
+            self.start()?;
+        }
+
+        let round = self.current_round;
+        let available = self.paired_players(round);
+
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.round = round;
+            trace.system = self.pairing_system;
+            trace.unavailable = self
+                .players
+                .keys()
+                .copied()
+                .filter(|id| !available.contains(id))
+                .collect();
         }
 
         match self.pairing_system {
-            PairingSystemType::BergerTable => BergerTablePairingSystem::pair_round(self),
-            PairingSystemType::ClassicSwiss => DutchPairingSystem::pair_round(self),
-            PairingSystemType::DubovSwiss => todo!(),
-            PairingSystemType::BursteinSwiss => todo!(),
-            PairingSystemType::Lim => todo!(),
-            PairingSystemType::None => todo!(),
+            PairingSystemType::BergerTable => {
+                self.run::<BergerTablePairingSystem>(&available, trace)
+            }
+            PairingSystemType::ClassicSwiss => self.run::<DutchPairingSystem>(&available, trace),
+            PairingSystemType::DubovSwiss => self.run::<DubovPairingSystem>(&available, trace),
+            PairingSystemType::BursteinSwiss => {
+                self.run::<BursteinPairingSystem>(&available, trace)
+            }
+            PairingSystemType::Lim => self.run::<LimPairingSystem>(&available, trace),
+            PairingSystemType::Scheveningen => {
+                self.run::<ScheveningenPairingSystem>(&available, trace)
+            }
+            PairingSystemType::None => unreachable!(),
+        }
+    }
+
+    #[inline]
+    fn run<P: PairngSystem>(
+        &mut self,
+        available: &BTreeSet<PlayerId>,
+        trace: Option<&mut PairingTrace>,
+    ) -> Result<Vec<Pairing>, Box<dyn Error>> {
+        match trace {
+            Some(trace) => P::pair_round_traced(self, available, trace),
+            None => P::pair_round(self, available),
+        }
+    }
+
+    // The players the pairing system pairs in the round, withdrawn players keeping their place
+    // on a fixed table included
+    pub(crate) fn paired_players(&self, round: u8) -> BTreeSet<PlayerId> {
+        self.players
+            .keys()
+            .copied()
+            .filter(|&id| self.is_available(id, round) || self.keeps_table_place(id, round))
+            .collect()
+    }
+
+    // Whether the player is paired in the round: not withdrawn, already entered and without a
+    // requested bye
+    #[must_use]
+    pub fn is_available(&self, player_id: PlayerId, round: u8) -> bool {
+        self.players.contains_key(&player_id)
+            && !self.is_withdrawn_in(player_id, round)
+            && !self
+                .late_entries
+                .iter()
+                .any(|&(id, joins)| id == player_id && joins > round)
+            && !self.has_requested_bye(player_id, round)
+    }
+
+    // A withdrawn player stays on a round-robin or Scheveningen table, the table needs a full field
+    pub(crate) fn keeps_table_place(&self, player_id: PlayerId, round: u8) -> bool {
+        self.has_fixed_table() && self.is_withdrawn_in(player_id, round)
+    }
+
+    #[inline]
+    const fn has_fixed_table(&self) -> bool {
+        matches!(
+            self.pairing_system,
+            PairingSystemType::BergerTable | PairingSystemType::Scheveningen
+        )
+    }
+
+    /// Records the result of a game in the current round
+    ///
+    /// # Errors
+    /// When the pairing is a bye, isn't part of the current round or has a withdrawn player,
+    /// whose game is forfeited already, or when the result is a bye's.
+    pub fn submit_result(
+        &mut self,
+        pairing: &Pairing,
+        result: GameResult,
+    ) -> Result<(), Box<dyn Error>> {
+        let Pairing::Game { left, right, .. } = *pairing else {
+            return Err("A bye doesn't take a result".into());
+        };
+        if result == GameResult::ZeroPointBye {
+            return Err("A game can't end in a bye".into());
+        }
+
+        let round = self
+            .rounds
+            .last()
+            .filter(|x| !x.is_finished())
+            .map(|x| x.number)
+            .ok_or("The pairing isn't part of the current round")?;
+
+        // Fixed tables forfeit the games of withdrawn players when pairing
+        if let Some(id) = [left, right]
+            .into_iter()
+            .find(|&x| self.is_withdrawn_in(x, round))
+        {
+            return Err(format!("Player {id} has withdrawn, the game is already forfeited").into());
+        }
+
+        let game = self
+            .rounds
+            .last_mut()
+            .and_then(|x| {
+                x.games
+                    .iter_mut()
+                    .find(|x| x.pairing.left() == left && x.pairing.right() == Some(right))
+            })
+            .ok_or("The pairing isn't part of the current round")?;
+
+        game.result = Some(result);
+        Ok(())
+    }
+
+    /// Gives an adjourned game of the current round its final result, the round can't be closed
+    /// or paired again before that
+    ///
+    /// # Errors
+    /// When the board of the current round isn't adjourned or the result is adjourned again.
+    pub fn resolve_adjourned(
+        &mut self,
+        board: u16,
+        result: GameResult,
+    ) -> Result<(), Box<dyn Error>> {
+        if result.is_adjourned() || !result.is_played() {
+            return Err("An adjourned game ends over the board".into());
+        }
+
+        let game = self
+            .rounds
+            .last_mut()
+            .filter(|x| !x.is_finished())
+            .and_then(|x| x.games.iter_mut().find(|x| x.board == board))
+            .filter(|x| x.result.is_some_and(GameResult::is_adjourned))
+            .ok_or_else(|| format!("Board {board} isn't adjourned"))?;
+
+        game.result = Some(result);
+        Ok(())
+    }
+
+    /// Takes the player out of every round that isn't paired yet, they stay in the standings
+    ///
+    /// # Errors
+    /// When the player isn't entered or has already withdrawn.
+    pub fn withdraw(&mut self, player_id: PlayerId) -> Result<(), Box<dyn Error>> {
+        if !self.players.contains_key(&player_id) {
+            return Err("Invalid player id".into());
+        }
+
+        if self.is_withdrawn(player_id) {
+            return Err(format!("Player {player_id} has already withdrawn").into());
         }
+
+        let round = self.rounds.last().map_or(1, |x| x.number + 1);
+        self.requested_byes
+            .retain(|&(number, id), _| id != player_id || number < round);
+        self.withdrawn_players.push((player_id, round));
+
+        Ok(())
+    }
+
+    /// Brings a withdrawn player back from the next round to be paired, the rounds missed in
+    /// between stay unplayed
+    ///
+    /// # Errors
+    /// When the player hasn't withdrawn.
+    pub fn reinstate(&mut self, player_id: PlayerId) -> Result<(), Box<dyn Error>> {
+        let index = self
+            .withdrawn_players
+            .iter()
+            .position(|&(id, _)| id == player_id)
+            .ok_or_else(|| format!("Player {player_id} hasn't withdrawn"))?;
+
+        self.withdrawn_players.remove(index);
+        Ok(())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_withdrawn(&self, player_id: PlayerId) -> bool {
+        self.withdrawn_players
+            .iter()
+            .any(|&(id, _)| id == player_id)
+    }
+
+    #[inline]
+    fn is_withdrawn_in(&self, player_id: PlayerId, round: u8) -> bool {
+        self.withdrawn_players
+            .iter()
+            .any(|&(id, from)| id == player_id && from <= round)
+    }
+
+    // Player and the first round they no longer play
+    #[inline]
+    #[must_use]
+    pub fn withdrawn_players(&self) -> &[(PlayerId, u8)] {
+        &self.withdrawn_players
+    }
+
+    // Player and the round they join
+    #[inline]
+    #[must_use]
+    pub fn late_entries(&self) -> &[(PlayerId, u8)] {
+        &self.late_entries
+    }
+
+    /// Enters a player who joins in `round`, every round before it scoring `missed` if any (e.g.
+    /// a half point bye), nothing otherwise. Until the fourth round is paired every pairing
+    /// number is given again by the initial ranking, afterwards the late entrant gets the last
+    /// one (C.04.2.B).
+    ///
+    /// # Errors
+    /// When the player is already entered, the tournament is a started round-robin or `round`
+    /// doesn't exist or is already paired.
+    pub fn add_late_entry(
+        &mut self,
+        mut player: Player,
+        round: u8,
+        missed: Option<ByeValue>,
+    ) -> Result<(), Box<dyn Error>> {
+        let player_id = player.info.id;
+        if self.players.contains_key(&player_id) {
+            return Err(format!("Player {player_id} is already entered").into());
+        }
+
+        if self.has_started() && self.has_fixed_table() {
+            return Err("A round-robin or Scheveningen can't take entries once started".into());
+        }
+
+        if round == 0 || round > self.number_of_rounds {
+            return Err(format!("There is no round {round}").into());
+        }
+
+        if self.rounds.iter().any(|x| x.number >= round) {
+            return Err(format!("Round {round} is already paired").into());
+        }
+
+        // Rounds already paired get a bye of their own, the finished ones are scored straight away
+        let result_scores = self.result_scores;
+        for previous in &mut self.rounds {
+            let Some(value) = missed else {
+                if previous.is_finished() {
+                    player.push_float(Float::Down);
+                }
+                continue;
+            };
+
+            let board = previous.games.len() as u16 + 1;
+            previous
+                .games
+                .push(Game::requested_bye(board, player_id, value.result()));
+
+            if previous.is_finished() {
+                player.score += value.result().scores(result_scores).0;
+                player.push_float(Float::Down);
+            }
+        }
+
+        if let Some(value) = missed {
+            let next = self.rounds.last().map_or(1, |x| x.number + 1);
+            for number in next..round {
+                self.requested_byes.insert((number, player_id), value);
+            }
+        }
+
+        let paired_rounds = self.rounds.len();
+        self.add_player(player);
+        self.late_entries.push((player_id, round));
+
+        if !self.has_started() {
+            return Ok(());
+        }
+
+        if paired_rounds < 4 && !self.lots_drawn {
+            self.assign_pairing_numbers();
+        } else {
+            let pairing_number = self.players.len() as u16;
+            if let Some(x) = self.players.get_mut(&player_id) {
+                x.pairing_number = pairing_number;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Puts the player in a team of a Scheveningen, boards go by pairing number within the team
+    ///
+    /// # Errors
+    /// When the tournament isn't a Scheveningen or has started, or the player isn't entered.
+    pub fn assign_team(&mut self, player_id: PlayerId, team: Team) -> Result<(), Box<dyn Error>> {
+        if self.pairing_system != PairingSystemType::Scheveningen {
+            return Err("Only a Scheveningen has teams".into());
+        }
+
+        if self.has_started() {
+            return Err("The teams of a Scheveningen are fixed once it has started".into());
+        }
+
+        if !self.players.contains_key(&player_id) {
+            return Err("Invalid player id".into());
+        }
+
+        self.teams.insert(player_id, team);
+        Ok(())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn team_of(&self, player_id: PlayerId) -> Option<Team> {
+        self.teams.get(&player_id).copied()
+    }
+
+    /// Registers a bye asked for in advance, scoring `value` for the player
+    ///
+    /// # Errors
+    /// When the tournament isn't a Swiss, `round` doesn't exist or is already paired, or the
+    /// player already has a bye that round or none left to request.
+    pub fn request_bye(
+        &mut self,
+        player_id: PlayerId,
+        round: u8,
+        value: ByeValue,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.players.contains_key(&player_id) {
+            return Err("Invalid player id".into());
+        }
+
+        // Fixed tables have no one to pair the rest of the field differently
+        if matches!(
+            self.pairing_system,
+            PairingSystemType::BergerTable | PairingSystemType::Scheveningen
+        ) {
+            return Err("Byes can only be requested in a Swiss".into());
+        }
+
+        if round == 0 || round > self.number_of_rounds {
+            return Err(format!("There is no round {round}").into());
+        }
+
+        if self.rounds.iter().any(|x| x.number >= round) {
+            return Err(format!("Round {round} is already paired").into());
+        }
+
+        if self.requested_byes.contains_key(&(round, player_id)) {
+            return Err(format!("Player {player_id} already has a bye in round {round}").into());
+        }
+
+        let count = self
+            .requested_byes
+            .keys()
+            .filter(|(_, id)| *id == player_id)
+            .count();
+        if self
+            .max_requested_byes
+            .is_some_and(|max| count >= usize::from(max))
+        {
+            return Err(format!("Player {player_id} has no byes left to request").into());
+        }
+
+        self.requested_byes.insert((round, player_id), value);
+        Ok(())
+    }
+
+    // Whether there was such a bye to cancel
+    #[inline]
+    pub fn cancel_bye(&mut self, player_id: PlayerId, round: u8) -> bool {
+        self.requested_byes.remove(&(round, player_id)).is_some()
+    }
+
+    #[inline]
+    pub fn requested_byes(&self, round: u8) -> impl Iterator<Item = (PlayerId, ByeValue)> {
+        self.requested_byes
+            .range((round, PlayerId::MIN)..=(round, PlayerId::MAX))
+            .map(|(&(_, id), &value)| (id, value))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn has_requested_bye(&self, player_id: PlayerId, round: u8) -> bool {
+        self.requested_byes.contains_key(&(round, player_id))
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn max_requested_byes(&self) -> Option<u8> {
+        self.max_requested_byes
+    }
+
+    // None means no limit
+    #[inline]
+    pub const fn set_max_requested_byes(&mut self, max: Option<u8>) {
+        self.max_requested_byes = max;
+    }
+
+    /// Applies every result of the current round to the players and moves on to the next round
+    ///
+    /// # Errors
+    /// When the current round isn't paired, a game has no result yet or is adjourned.
+    pub fn close_round(&mut self) -> Result<(), Box<dyn Error>> {
+        let round = self
+            .rounds
+            .pop()
+            .filter(|x| !x.is_finished())
+            .ok_or("The current round hasn't been paired")?;
+
+        let adjourned = round.adjourned_boards().next();
+        if let Some(board) = adjourned {
+            self.rounds.push(round);
+            return Err(format!("The game on board {board} is adjourned").into());
+        }
+
+        if !round.has_all_results() {
+            self.rounds.push(round);
+            return Err("Not every game of the current round has a result".into());
+        }
+
+        self.finish_round(round)
+    }
+
+    // Rounds with every result are applied, otherwise the round is the one being played
+    pub(crate) fn load_round(&mut self, round: Round) -> Result<(), Box<dyn Error>> {
+        if round.has_all_results() {
+            return self.finish_round(round);
+        }
+
+        self.rounds.push(round);
+        Ok(())
+    }
+
+    // Applies the results of a complete round, which becomes the last finished one
+    pub(crate) fn finish_round(&mut self, mut round: Round) -> Result<(), Box<dyn Error>> {
+        // Floats are decided by the pairing scores before the round
+        let scores = self
+            .players
+            .iter()
+            .map(|(id, player)| (*id, self.pairing_score(player)))
+            .collect::<BTreeMap<_, _>>();
+
+        for game in &round.games {
+            let (pairing, result) = (&game.pairing, game.result);
+            let result = result.ok_or("Not every game of the round has a result")?;
+            let (left_points, right_points) = result.scores(self.result_scores);
+
+            let Pairing::Game {
+                left,
+                right,
+                color_left,
+                color_right,
+            } = *pairing
+            else {
+                let player = self
+                    .get_player_mut(pairing.left())
+                    .ok_or("Invalid player id")?;
+                player.score += left_points;
+                player.has_bye |= game.is_allocated_bye();
+                player.push_float(Float::Down);
+                continue;
+            };
+
+            // A forfeit win rules out the pairing-allocated bye just as well (C.04.1.d)
+            let forfeit_winner = match result {
+                GameResult::LeftWinsByForfeit => Some(left),
+                GameResult::RightWinsByForfeit => Some(right),
+                _ => None,
+            };
+
+            for (id, opponent, color, points) in [
+                (left, right, color_left, left_points),
+                (right, left, color_right, right_points),
+            ] {
+                // A player who doesn't play, for whatever reason, counts as downfloated
+                let float = if result.is_played() {
+                    match scores.get(&id).cmp(&scores.get(&opponent)) {
+                        Ordering::Greater => Float::Down,
+                        Ordering::Less => Float::Up,
+                        Ordering::Equal => Float::None,
+                    }
+                } else {
+                    Float::Down
+                };
+
+                let player = self.get_player_mut(id).ok_or("Invalid player id")?;
+                player.score += points;
+                player.has_bye |= forfeit_winner == Some(id);
+                player.push_float(float);
+
+                if result.is_played() {
+                    player.previous_opponents.push(opponent);
+                    player.push_color(color);
+                }
+            }
+        }
+
+        // Players without a game didn't play either
+        for (id, player) in &mut self.players {
+            if round.game_of(*id).is_none() {
+                player.push_float(Float::Down);
+            }
+        }
+
+        round.finished_at = Some(SystemTime::now());
+        self.rounds.push(round);
+        self.bump_round();
+
+        Ok(())
     }
 
     pub(crate) fn assign_pairing_numbers(&mut self) {
@@ -127,23 +883,87 @@ impl Tournament {
         }
     }
 
+    // Players grouped by their real score, as ranked in the standings
+    #[inline]
+    #[must_use]
     pub fn get_score_groups(&self) -> ScoreGroups {
+        self.group_by_score(|player| player.score)
+    }
+
+    // Players grouped by the score the pairing systems see, accelerated if need be
+    #[inline]
+    #[must_use]
+    pub fn get_pairing_score_groups(&self) -> ScoreGroups {
+        self.group_by_score(|player| self.pairing_score(player))
+    }
+
+    fn group_by_score(&self, score: impl Fn(&Player) -> u8) -> ScoreGroups {
         let mut scoregroup = ScoreGroups::new();
 
         self.players.values().for_each(|player| {
-            if let btree_map::Entry::Vacant(entry) = scoregroup.entry(player.score) {
+            let score = score(player);
+            if let btree_map::Entry::Vacant(entry) = scoregroup.entry(score) {
                 entry.insert(smallvec![player.info.id]);
             } else {
                 // SAFETY: We check if the key is present or not
-                unsafe { scoregroup.get_mut(&player.score).unwrap_unchecked() }
-                    .push(player.info.id);
+                unsafe { scoregroup.get_mut(&score).unwrap_unchecked() }.push(player.info.id);
             }
         });
 
         scoregroup
     }
 
+    // The real score plus the virtual points of the current round
     #[inline]
+    #[must_use]
+    pub fn pairing_score(&self, player: &Player) -> u8 {
+        player.score
+            + self
+                .acceleration
+                .virtual_points(self, player, self.current_round)
+    }
+
+    // Gives every player a pairing number by lot, e.g. for a round-robin. The same seed draws
+    // the same numbers, so the drawing can be published and replayed.
+    pub fn draw_lots(&mut self, rng: &mut Rng) {
+        let mut ids = self.players.keys().copied().collect::<Vec<_>>();
+        rng.shuffle(&mut ids);
+
+        // The first drawn gets pairing number 1
+        for (id, pairing_number) in ids.iter().zip(1..) {
+            if let Some(player) = self.players.get_mut(id) {
+                player.pairing_number = pairing_number;
+            }
+        }
+
+        self.lots_drawn = true;
+    }
+
+    /// Draws the pairing numbers of a round-robin from the Varma tables, keeping players of the
+    /// same federation or club from meeting in the last rounds
+    ///
+    /// # Errors
+    /// When there are no Varma tables for the number of players.
+    pub fn draw_lots_varma(
+        &mut self,
+        rng: &mut Rng,
+        affiliation: Affiliation,
+    ) -> Result<(), Box<dyn Error>> {
+        let players = self.players.values().map(|x| &*x.info).collect::<Vec<_>>();
+        let numbers = varma::allocate(&players, affiliation, rng)?;
+
+        for (id, number) in numbers {
+            if let Some(player) = self.players.get_mut(&id) {
+                player.pairing_number = number as u16;
+            }
+        }
+
+        self.lots_drawn = true;
+        Ok(())
+    }
+
+    #[inline]
+    #[must_use]
     pub const fn current_round(&self) -> u8 {
         self.current_round
     }
@@ -153,23 +973,86 @@ impl Tournament {
         self.current_round += 1;
     }
 
+    // Starts the tournament with the pairing numbers already on the players
     #[inline]
-    pub fn start(&mut self) {
-        self.assign_pairing_numbers();
+    pub(crate) const fn resume(&mut self) {
         self.current_round = 1;
     }
 
+    /// Gives the players their pairing numbers, unless lots were drawn for them, and moves on
+    /// to the first round
+    ///
+    /// # Errors
+    /// When the tournament is a round-robin and no lots were drawn, see `draw_lots`.
     #[inline]
+    pub fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.pairing_system == PairingSystemType::BergerTable && !self.lots_drawn {
+            return Err("Lots must be drawn for the pairing numbers of a round-robin".into());
+        }
+
+        if !self.lots_drawn {
+            self.assign_pairing_numbers();
+        }
+
+        self.current_round = 1;
+        Ok(())
+    }
+
+    #[inline]
+    #[must_use]
     pub const fn has_started(&self) -> bool {
         self.current_round > 0
     }
 
     #[inline]
+    #[must_use]
     pub fn player_count(&self) -> usize {
         self.players.len()
     }
 
     #[inline]
+    #[must_use]
+    pub fn get_rounds(&self) -> &[Round] {
+        &self.rounds
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_round(&self, number: u8) -> Option<&Round> {
+        self.rounds.iter().find(|x| x.number == number)
+    }
+
+    #[inline]
+    pub fn finished_rounds(&self) -> impl Iterator<Item = &Round> {
+        self.rounds.iter().filter(|x| x.is_finished())
+    }
+
+    // Every game of the player with a final result, along with its round number
+    pub fn games_of_player(&self, player_id: PlayerId) -> impl Iterator<Item = (u8, &Game)> {
+        self.rounds.iter().filter_map(move |round| {
+            round
+                .game_of(player_id)
+                .filter(|x| x.result.is_some_and(|x| !x.is_adjourned()))
+                .map(|x| (round.number, x))
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn result_of(&self, round: u8, board: u16) -> Option<GameResult> {
+        self.get_round(round)?.get_game(board)?.result
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn opponent_in_round(&self, player_id: PlayerId, round: u8) -> Option<PlayerId> {
+        self.get_round(round)?
+            .game_of(player_id)?
+            .opponent_of(player_id)
+    }
+
+    #[inline]
+    #[must_use]
     pub const fn get_players(&self) -> &BTreeMap<PlayerId, Player> {
         &self.players
     }
@@ -180,6 +1063,10 @@ impl Tournament {
     }
 
     // I should probably put this in a trait
+    /// The settings and the players as plain text
+    ///
+    /// # Errors
+    /// When a withdrawn player isn't entered.
     pub fn as_string_csv_like(&mut self) -> Result<String, Box<dyn Error>> {
         let mut buffer = String::new();
         buffer.write_fmt(format_args!(
@@ -214,9 +1101,166 @@ impl Tournament {
             .sorted_by_key(|x| x.1.pairing_number)
             .rev()
         {
-            buffer.write_fmt(format_args!("{}\n", player.1.as_string_csv_like(),))?;
+            buffer.write_fmt(format_args!("{}\n", player.1.as_string_csv_like()))?;
         }
 
         Ok(buffer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::chess_types::{ByeValue, Color, GameResult, Title};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::Player;
+    use crate::tiebreak::Tiebreak;
+    use crate::tournament::Tournament;
+    use crate::trf;
+
+    // The round waits for the adjourned game, which counts for nothing until it's resolved
+    #[test]
+    fn adjourned_games_hold_up_the_round() {
+        let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        for id in 1..=4 {
+            let rating = 2500 - 100 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        tournament.start().unwrap();
+        let pairings = tournament.pair().unwrap();
+        let adjourned = &pairings[1];
+        let (left, right) = (adjourned.left(), adjourned.right().unwrap());
+
+        tournament
+            .submit_result(&pairings[0], GameResult::LeftWins)
+            .unwrap();
+        tournament
+            .submit_result(adjourned, GameResult::Adjourned)
+            .unwrap();
+
+        assert!(tournament.close_round().is_err());
+        assert!(tournament.pair().is_err());
+        assert_eq!(tournament.games_of_player(left).count(), 0);
+        assert_eq!(tournament.games_of_player(right).count(), 0);
+        assert!(tournament.resolve_adjourned(1, GameResult::Draw).is_err());
+        assert!(
+            tournament
+                .resolve_adjourned(2, GameResult::Adjourned)
+                .is_err()
+        );
+
+        tournament
+            .resolve_adjourned(2, GameResult::RightWins)
+            .unwrap();
+        tournament.close_round().unwrap();
+
+        let (win, _, lose) = tournament.result_scores();
+        for (id, score) in [(left, lose), (right, win)] {
+            let player = tournament.get_player(id).unwrap();
+            assert_eq!(player.score, score);
+            assert_eq!(player.color_history.len(), 1);
+            assert_eq!(tournament.games_of_player(id).count(), 1);
+        }
+    }
+
+    // Player 6 sits the round out but still counts for the ARO of player 2, who met them. The
+    // white seekers go by ascending ARO, so player 4 is paired first.
+    #[test]
+    fn players_sitting_out_still_count_for_their_opponents() {
+        let mut tournament = Tournament::new(3, PairingSystemType::DubovSwiss);
+        for id in 1..=8 {
+            let rating = 2450 - 50 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        tournament.start().unwrap();
+        tournament.request_bye(6, 2, ByeValue::Draw).unwrap();
+
+        for pairing in tournament.pair().unwrap() {
+            let top_wins = if pairing.left() < pairing.right().unwrap() {
+                GameResult::LeftWins
+            } else {
+                GameResult::RightWins
+            };
+            tournament.submit_result(&pairing, top_wins).unwrap();
+        }
+
+        tournament.close_round().unwrap();
+        assert_eq!(
+            Tiebreak::AverageRatingOfOpponents.compute(&tournament, 2, &[]),
+            2150
+        );
+
+        let (pairings, trace) = tournament.pair_with_trace().unwrap();
+        assert_eq!(trace.unavailable, [6]);
+        assert_eq!(tournament.player_count(), 8);
+
+        let white = |id| {
+            pairings
+                .iter()
+                .find(|x| x.left() == id || x.right() == Some(id))
+                .map(|x| (x.left(), x.right(), x.colors().unwrap().0))
+                .unwrap()
+        };
+        assert_eq!(white(4), (4, Some(1), Color::White));
+        assert_eq!(white(2), (2, Some(3), Color::White));
+    }
+
+    // A zero-point bye scores nothing and isn't a game, neither for the player nor for the TRF
+    #[test]
+    fn zero_point_byes_are_not_played() {
+        let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        for id in 1..=5 {
+            let rating = 2500 - 100 * id as u16;
+            tournament.add_player(Player::new(
+                id,
+                &format!("Player {id}"),
+                Title::None,
+                rating,
+            ));
+        }
+
+        tournament.start().unwrap();
+        tournament.request_bye(5, 1, ByeValue::Loss).unwrap();
+
+        let pairings = tournament.pair().unwrap();
+        assert!(pairings.iter().all(|x| !x.is_bye()));
+        assert!(
+            tournament
+                .submit_result(&pairings[0], GameResult::ZeroPointBye)
+                .is_err()
+        );
+        for pairing in &pairings {
+            tournament.submit_result(pairing, GameResult::Draw).unwrap();
+        }
+        tournament.close_round().unwrap();
+
+        let (_, bye) = tournament.games_of_player(5).next().unwrap();
+        assert!(bye.is_bye());
+        assert_eq!(bye.result, Some(GameResult::ZeroPointBye));
+        assert!(!bye.is_played());
+        assert_eq!(bye.color_of(5), None);
+
+        let player = tournament.get_player(5).unwrap();
+        assert_eq!(player.score, 0);
+        assert!(player.previous_opponents.is_empty());
+
+        let parsed = trf::parse(
+            &trf::write(&tournament).unwrap(),
+            PairingSystemType::ClassicSwiss,
+        )
+        .unwrap();
+        let (_, bye) = parsed.games_of_player(5).next().unwrap();
+        assert_eq!(bye.result, Some(GameResult::ZeroPointBye));
+    }
+}
@@ -0,0 +1,384 @@
+use std::fmt;
+
+use itertools::Itertools as _;
+
+use crate::pairing::Pairing;
+use crate::pairing_system::PairingSystemType;
+use crate::player::PlayerId;
+
+// How a round was paired, for the arbiter to explain it. Pairing with a trace gives the same
+// pairings as without.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PairingTrace {
+    pub round: u8,
+    pub system: PairingSystemType,
+    // Withdrawn, not entered yet or with a requested bye
+    pub unavailable: Vec<PlayerId>,
+    // In the order they were paired, which is from the highest score down but for Lim
+    pub brackets: Vec<BracketTrace>,
+    // In board order, the bye last
+    pub boards: Vec<BoardTrace>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BracketTrace {
+    // Pairing score of the residents
+    pub score: u8,
+    pub residents: Vec<PlayerId>,
+    // Moved down from the brackets above (MDPs)
+    pub downfloaters: Vec<PlayerId>,
+    // Moved up from the brackets below, in Dubov and under the median in Lim
+    pub upfloaters: Vec<PlayerId>,
+    // Split of the homogeneous part the chosen pairing came from
+    pub s1: Vec<PlayerId>,
+    pub s2: Vec<PlayerId>,
+    pub exchanges_tried: usize,
+    pub transpositions_tried: usize,
+    // Pairs turned down by C1 or C3
+    pub incompatible: usize,
+    // Candidates turned down because the rest couldn't be paired (C4)
+    pub incomplete: usize,
+    pub outcome: BracketOutcome,
+    pub pairs: Vec<(PlayerId, PlayerId)>,
+    // Floating on to the next bracket, or the bye in the last one
+    pub floaters: Vec<PlayerId>,
+    // Quality criteria where the chosen pairing falls short of the best conceivable one
+    pub shortfalls: Vec<Shortfall>,
+}
+
+// What the search of a Dubov, Burstein or Lim score group went through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct Tries {
+    pub(crate) exchanges: usize,
+    // Pairs taken back to try another opponent
+    pub(crate) transpositions: usize,
+    pub(crate) incompatible: usize,
+    pub(crate) incomplete: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BracketOutcome {
+    #[default]
+    Paired,
+    // No pairing kept the rest pairable, everyone moved down
+    MovedDown,
+    // The last bracket took a maximum matching of whoever was left
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortfall {
+    pub criterion: &'static str,
+    pub chosen: u32,
+    pub ideal: u32,
+}
+
+impl Shortfall {
+    #[inline]
+    pub(crate) fn of(criterion: &'static str, chosen: usize, ideal: usize) -> Option<Self> {
+        (chosen > ideal).then_some(Self {
+            criterion,
+            chosen: chosen as u32,
+            ideal: ideal as u32,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardTrace {
+    pub board: u16,
+    pub pairing: Pairing,
+    // None for the bye, or if the pairing system doesn't say
+    pub rule: Option<ColorRule>,
+}
+
+// Why a board got its colours. Dubov and Lim apply the rules of the Dutch system (C.04.3 E.1-E.5)
+// in their own way, so only Dutch boards are labelled with the articles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorRule {
+    // The top player of every odd board gets the initial colour
+    FirstRound,
+    BothPreferences,
+    StrongerPreference,
+    ColorHistory,
+    HigherRankedPreference,
+    PairingNumber,
+    // Dubov: the white seeker gets white, no preference being granted
+    WhiteSeeker,
+}
+
+impl BracketTrace {
+    #[inline]
+    pub(crate) const fn set_tries(&mut self, tries: Tries) {
+        self.exchanges_tried = tries.exchanges;
+        self.transpositions_tried = tries.transpositions;
+        self.incompatible = tries.incompatible;
+        self.incomplete = tries.incomplete;
+    }
+}
+
+impl ColorRule {
+    #[inline]
+    #[must_use]
+    pub const fn dutch_article(self) -> Option<&'static str> {
+        match self {
+            Self::BothPreferences => Some("E.1"),
+            Self::StrongerPreference => Some("E.2"),
+            Self::ColorHistory => Some("E.3"),
+            Self::HigherRankedPreference => Some("E.4"),
+            Self::PairingNumber => Some("E.5"),
+            Self::FirstRound | Self::WhiteSeeker => None,
+        }
+    }
+}
+
+impl PairingTrace {
+    // Boards in the order of the pairings, for pairing systems that don't explain the colours
+    pub(crate) fn set_boards(&mut self, pairings: &[Pairing]) {
+        self.boards = pairings
+            .iter()
+            .zip(1..)
+            .map(|(pairing, board)| BoardTrace {
+                board,
+                pairing: pairing.clone(),
+                rule: None,
+            })
+            .collect();
+    }
+}
+
+impl fmt::Display for ColorRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FirstRound => write!(f, "initial colour, alternating by board"),
+            Self::BothPreferences => write!(f, "both preferences granted"),
+            Self::StrongerPreference => write!(f, "stronger preference granted"),
+            Self::ColorHistory => write!(f, "alternated from the last differing round"),
+            Self::HigherRankedPreference => write!(f, "higher ranked player's preference"),
+            Self::PairingNumber => write!(f, "by pairing number and initial colour"),
+            Self::WhiteSeeker => write!(f, "white seeker gets white"),
+        }
+    }
+}
+
+impl fmt::Display for BracketOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Paired => write!(f, "paired"),
+            Self::MovedDown => write!(f, "no valid pairing, everyone moves down"),
+            Self::Completed => write!(f, "completed by a maximum matching"),
+        }
+    }
+}
+
+impl fmt::Display for PairingTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids = |x: &[PlayerId]| x.iter().join(" ");
+
+        writeln!(f, "Round {}", self.round)?;
+        if !self.unavailable.is_empty() {
+            writeln!(f, "Not paired: {}", ids(&self.unavailable))?;
+        }
+
+        for bracket in &self.brackets {
+            writeln!(
+                f,
+                "\nBracket of score {}: {}",
+                bracket.score, bracket.outcome
+            )?;
+            writeln!(f, "  Residents: {}", ids(&bracket.residents))?;
+            if !bracket.downfloaters.is_empty() {
+                writeln!(f, "  Downfloaters: {}", ids(&bracket.downfloaters))?;
+            }
+            if !bracket.upfloaters.is_empty() {
+                writeln!(f, "  Upfloaters: {}", ids(&bracket.upfloaters))?;
+            }
+            if !bracket.s1.is_empty() || !bracket.s2.is_empty() {
+                writeln!(f, "  S1: {}  S2: {}", ids(&bracket.s1), ids(&bracket.s2))?;
+            }
+            if bracket.exchanges_tried
+                + bracket.transpositions_tried
+                + bracket.incompatible
+                + bracket.incomplete
+                > 0
+            {
+                writeln!(
+                    f,
+                    "  Tried {} exchange(s) and {} transposition(s), {} pair(s) ruled out by \
+                     C1/C3, {} candidate(s) by C4",
+                    bracket.exchanges_tried,
+                    bracket.transpositions_tried,
+                    bracket.incompatible,
+                    bracket.incomplete
+                )?;
+            }
+            if !bracket.pairs.is_empty() {
+                let pairs = bracket.pairs.iter().map(|(a, b)| format!("{a}-{b}"));
+                writeln!(f, "  Pairs: {}", pairs.format(" "))?;
+            }
+            if !bracket.floaters.is_empty() {
+                writeln!(f, "  Floaters: {}", ids(&bracket.floaters))?;
+            }
+            for x in &bracket.shortfalls {
+                writeln!(f, "  {}: {}, ideally {}", x.criterion, x.chosen, x.ideal)?;
+            }
+        }
+
+        writeln!(f, "\nBoards:")?;
+        for board in &self.boards {
+            let pairing = &board.pairing;
+            let Some((white, black)) = pairing.white_and_black() else {
+                writeln!(f, "  {:>3}  {} bye", board.board, pairing.left())?;
+                continue;
+            };
+            write!(f, "  {:>3}  {white} - {black}", board.board)?;
+            let article = board
+                .rule
+                .and_then(ColorRule::dutch_article)
+                .filter(|_| self.system == PairingSystemType::ClassicSwiss);
+            match (article, board.rule) {
+                (Some(article), Some(rule)) => writeln!(f, "  ({article} {rule})")?,
+                (None, Some(rule)) => writeln!(f, "  ({rule})")?,
+                (_, None) => writeln!(f)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoardTrace, BracketOutcome, BracketTrace, ColorRule, PairingTrace, Shortfall};
+    use crate::chess_types::Color;
+    use crate::pairing::Pairing;
+    use crate::pairing_system::PairingSystemType;
+    use crate::trf;
+
+    fn trace(system: PairingSystemType) -> PairingTrace {
+        PairingTrace {
+            round: 3,
+            system,
+            unavailable: vec![6],
+            brackets: vec![
+                BracketTrace {
+                    score: 4,
+                    residents: vec![1, 2, 3],
+                    upfloaters: vec![4],
+                    s1: vec![1, 2],
+                    s2: vec![3, 4],
+                    exchanges_tried: 1,
+                    transpositions_tried: 2,
+                    incompatible: 3,
+                    incomplete: 4,
+                    pairs: vec![(1, 4), (2, 3)],
+                    shortfalls: vec![Shortfall {
+                        criterion: "Floaters",
+                        chosen: 2,
+                        ideal: 0,
+                    }],
+                    ..Default::default()
+                },
+                BracketTrace {
+                    score: 0,
+                    residents: vec![5],
+                    downfloaters: vec![7],
+                    outcome: BracketOutcome::MovedDown,
+                    floaters: vec![5, 7],
+                    ..Default::default()
+                },
+            ],
+            boards: vec![
+                BoardTrace {
+                    board: 1,
+                    pairing: Pairing::new(1, 4, Color::Black, Color::White),
+                    rule: Some(ColorRule::BothPreferences),
+                },
+                BoardTrace {
+                    board: 2,
+                    pairing: Pairing::new(2, 3, Color::White, Color::Black),
+                    rule: None,
+                },
+                BoardTrace {
+                    board: 3,
+                    pairing: Pairing::bye(5),
+                    rule: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            trace(PairingSystemType::ClassicSwiss).to_string(),
+            "Round 3\n\
+             Not paired: 6\n\
+             \n\
+             Bracket of score 4: paired\n\
+             \x20 Residents: 1 2 3\n\
+             \x20 Upfloaters: 4\n\
+             \x20 S1: 1 2  S2: 3 4\n\
+             \x20 Tried 1 exchange(s) and 2 transposition(s), 3 pair(s) ruled out by C1/C3, 4 \
+             candidate(s) by C4\n\
+             \x20 Pairs: 1-4 2-3\n\
+             \x20 Floaters: 2, ideally 0\n\
+             \n\
+             Bracket of score 0: no valid pairing, everyone moves down\n\
+             \x20 Residents: 5\n\
+             \x20 Downfloaters: 7\n\
+             \x20 Floaters: 5 7\n\
+             \n\
+             Boards:\n\
+             \x20   1  4 - 1  (E.1 both preferences granted)\n\
+             \x20   2  2 - 3\n\
+             \x20   3  5 bye\n"
+        );
+    }
+
+    // Dubov and Lim apply the same rules in their own order
+    #[test]
+    fn only_dutch_boards_get_articles() {
+        for system in [PairingSystemType::DubovSwiss, PairingSystemType::Lim] {
+            let rendered = trace(system).to_string();
+            assert!(
+                rendered.contains("  (both preferences granted)"),
+                "{rendered}"
+            );
+            assert!(!rendered.contains("E.1"), "{rendered}");
+        }
+    }
+
+    // Everyone drew round 1, 1-3 and 2-4, so 1 and 4 seek black, 2 and 3 white
+    #[test]
+    fn other_systems_trace_their_search() {
+        let everyone_drew = |system| {
+            trf::replay(
+                &[2400, 2300, 2200, 2100],
+                &[
+                    &[(3, 'w', '=')],
+                    &[(4, 'b', '=')],
+                    &[(1, 'b', '=')],
+                    &[(2, 'w', '=')],
+                ],
+                5,
+                system,
+            )
+        };
+
+        let (_, trace) = everyone_drew(PairingSystemType::DubovSwiss)
+            .pair_with_trace()
+            .unwrap();
+        assert_eq!(trace.system, PairingSystemType::DubovSwiss);
+        assert_eq!(trace.brackets[0].s1, [2, 3]);
+        assert_eq!(trace.brackets[0].s2, [1, 4]);
+
+        // Both try 1 against 3 first
+        for system in [PairingSystemType::BursteinSwiss, PairingSystemType::Lim] {
+            let (_, trace) = everyone_drew(system).pair_with_trace().unwrap();
+            let bracket = trace.brackets.last().unwrap();
+            assert_eq!(bracket.incompatible, 1, "{system:?}");
+            assert!(bracket.shortfalls.is_empty(), "{system:?}");
+        }
+    }
+}
@@ -0,0 +1,888 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use itertools::Itertools as _;
+
+use crate::chess_types::{ByeValue, Color, GameResult, ResultScoreConfig, Sex, Title};
+use crate::pairing::Pairing;
+use crate::pairing_system::PairingSystemType;
+use crate::player::{Player, PlayerId, PlayerInfo};
+use crate::round::{Bye, Game, Round};
+use crate::tournament::{Tournament, TournamentInfo};
+
+// FIDE TRF16, https://www.fide.com/FIDE/handbook/C04Annex2_TRF16.pdf
+// Columns are 1-based and inclusive, as in the specification
+
+// Every round takes 10 columns of a player line, starting at column 92
+const FIRST_ROUND_COLUMN: usize = 92;
+const ROUND_WIDTH: usize = 10;
+
+// A player's line in one round: opponent's starting rank (0 for none), colour, result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RoundEntry {
+    opponent: usize,
+    color: char,
+    result: char,
+}
+
+struct PlayerLine {
+    rank: usize,
+    info: PlayerInfo,
+    rounds: Vec<Option<RoundEntry>>,
+}
+
+/// Reads a TRF16 file, see `parse`
+///
+/// # Errors
+/// When the file can't be read or isn't valid TRF16.
+pub fn read(
+    path: impl AsRef<Path>,
+    pairing_system: PairingSystemType,
+) -> Result<Tournament, Box<dyn Error>> {
+    parse(&std::fs::read_to_string(path)?, pairing_system)
+}
+
+/// Players get their starting rank as id. Results are replayed round by round, so scores,
+/// colours and floats are the ones the tournament would have recorded itself.
+///
+/// # Errors
+/// When a line is malformed, the starting ranks aren't consecutive or a round can't be
+/// replayed.
+pub fn parse(input: &str, pairing_system: PairingSystemType) -> Result<Tournament, Box<dyn Error>> {
+    let mut players = Vec::new();
+    let mut number_of_rounds = None;
+    let mut initial_color = Color::None;
+    let mut points = BTreeMap::new();
+    let mut info = TournamentInfo::default();
+
+    for line in input.lines() {
+        let line = line.trim_end().chars().collect::<Vec<_>>();
+        let Some(code) = line.get(..3) else {
+            continue;
+        };
+
+        let value = field(&line, 5, line.len());
+        match code.iter().collect::<String>().as_str() {
+            "001" => players.push(parse_player(&line)?),
+            "012" => info.name = value,
+            "022" => info.city = value,
+            "032" => info.federation = value,
+            "042" => info.start_date = value,
+            "052" => info.end_date = value,
+            "092" => info.kind = value,
+            "102" => info.chief_arbiter = value,
+            "112" => info.deputy_arbiters.push(value),
+            "122" => info.time_control = value,
+            "132" => {
+                info.round_dates = field(&line, FIRST_ROUND_COLUMN, line.len())
+                    .split_whitespace()
+                    .map(ToOwned::to_owned)
+                    .collect();
+            }
+            "XXR" => number_of_rounds = Some(value.parse::<u8>()?),
+            "XXC" => {
+                for token in value.split_whitespace() {
+                    match token {
+                        "white1" => initial_color = Color::White,
+                        "black1" => initial_color = Color::Black,
+                        _ => {}
+                    }
+                }
+            }
+            "XXS" => {
+                for token in value.split_whitespace() {
+                    let (code, value) = token
+                        .split_once('=')
+                        .ok_or_else(|| format!("Invalid scoring \"{token}\""))?;
+                    points.insert(code.to_owned(), value.parse::<f32>()?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    players.sort_unstable_by_key(|x| x.rank);
+    for (i, player) in players.iter().enumerate() {
+        if player.rank != i + 1 {
+            return Err(format!("Starting ranks aren't 1 to {}", players.len()).into());
+        }
+    }
+
+    let rounds_seen = players.iter().map(|x| x.rounds.len()).max().unwrap_or(0);
+    let number_of_rounds = number_of_rounds.unwrap_or(rounds_seen as u8);
+    if rounds_seen > usize::from(number_of_rounds) {
+        return Err("More rounds played than the tournament has".into());
+    }
+
+    let result_scores = result_scores(&points)?;
+    let bye_value = bye_value(&points, result_scores)?;
+    let mut tournament =
+        Tournament::new_with_result_scores(number_of_rounds, pairing_system, result_scores);
+    tournament.set_bye_value(bye_value);
+    *tournament.get_info_mut() = info;
+    tournament.set_initial_color(initial_color);
+
+    for line in &players {
+        let mut player = Player::new(
+            line.info.id,
+            &line.info.name,
+            line.info.title,
+            line.info.rating,
+        );
+        player.info = Box::new(line.info.clone());
+        player.pairing_number = line.rank as u16;
+        tournament.add_player(player);
+    }
+
+    tournament.resume();
+
+    for round in 0..rounds_seen {
+        let number = tournament.current_round();
+        let round = build_round(&players, round, number, bye_value)?;
+
+        if round.games.is_empty() {
+            break;
+        }
+
+        let is_finished = round.has_all_results();
+        if !is_finished && usize::from(number) < rounds_seen {
+            return Err(
+                format!("Round {number} isn't finished, but later rounds are paired").into(),
+            );
+        }
+
+        tournament.load_round(round)?;
+
+        if !is_finished {
+            break;
+        }
+    }
+
+    Ok(tournament)
+}
+
+/// Writes the tournament to a TRF16 file
+///
+/// # Errors
+/// When the file can't be written.
+pub fn save(tournament: &Tournament, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, write(tournament)?)?;
+    Ok(())
+}
+
+/// Starting ranks come from the pairing numbers, the rank column from the standings
+///
+/// # Errors
+/// When formatting a line fails.
+pub fn write(tournament: &Tournament) -> Result<String, Box<dyn Error>> {
+    let info = tournament.get_info();
+    let players = tournament.get_players();
+    let mut buffer = String::new();
+
+    let rated = players.values().filter(|x| x.info.rating > 0).count();
+    let counts = [players.len().to_string(), rated.to_string(), "0".to_owned()];
+
+    let header = [
+        ("012", &info.name),
+        ("022", &info.city),
+        ("032", &info.federation),
+        ("042", &info.start_date),
+        ("052", &info.end_date),
+        ("062", &counts[0]),
+        ("072", &counts[1]),
+        ("082", &counts[2]),
+        ("092", &info.kind),
+        ("102", &info.chief_arbiter),
+    ]
+    .into_iter()
+    .chain(info.deputy_arbiters.iter().map(|x| ("112", x)))
+    .chain([("122", &info.time_control)]);
+
+    // Unknown details are left out
+    for (code, value) in header.filter(|(_, value)| !value.is_empty()) {
+        writeln!(buffer, "{code} {value}")?;
+    }
+
+    if !info.round_dates.is_empty() {
+        let dates = info
+            .round_dates
+            .iter()
+            .map(|x| format!("{x:<8}"))
+            .join("  ");
+        writeln!(
+            buffer,
+            "132 {:width$}{dates}",
+            "",
+            width = FIRST_ROUND_COLUMN - 5
+        )?;
+    }
+
+    let ranks = starting_ranks(tournament);
+    let places = tournament
+        .standings()
+        .into_iter()
+        .map(|x| (x.player_id, x.place))
+        .collect::<BTreeMap<_, _>>();
+    let result_scores = tournament.result_scores();
+
+    for (id, rank) in ranks.iter().sorted_by_key(|x| x.1) {
+        let player = &players[id];
+        let sex = match player.info.sex {
+            Sex::Male => 'm',
+            Sex::Female => 'w',
+            Sex::Unknown => ' ',
+        };
+        let points = to_points(player.score, result_scores);
+
+        let mut line = format!(
+            "001 {rank:>4} {sex}{:>3} {:<33} {:>4} {:<3} {:>11} {:<10} {points:>4.1} {:>4}",
+            title_code(player.info.title),
+            player.info.name.chars().take(33).collect::<String>(),
+            if player.info.rating > 0 {
+                player.info.rating.to_string()
+            } else {
+                String::new()
+            },
+            player.info.federation,
+            player
+                .info
+                .fide_id
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+            player.info.birth_date,
+            places.get(id).copied().unwrap_or(0),
+        );
+
+        // Rounds without an opponent show 0000, whether the player had a bye or wasn't paired
+        for round in tournament.get_rounds() {
+            match round.game_of(*id).map(|x| round_entry(x, *id, &ranks)) {
+                Some((Some(opponent), color, result)) => {
+                    write!(line, "  {opponent:>4} {color} {result}")?;
+                }
+                Some((None, color, result)) => write!(line, "  0000 {color} {result}")?,
+                None => write!(line, "  0000 - -")?,
+            }
+        }
+
+        writeln!(buffer, "{}", line.trim_end())?;
+    }
+
+    let (win, draw, lose) = result_scores;
+    writeln!(buffer, "XXR {}", tournament.number_of_rounds())?;
+    writeln!(
+        buffer,
+        "XXS W={} D={} L={} PAB={}",
+        to_points(win, result_scores),
+        to_points(draw, result_scores),
+        to_points(lose, result_scores),
+        to_points(
+            tournament.bye_value().result().scores(result_scores).0,
+            result_scores
+        ),
+    )?;
+    match tournament.initial_color() {
+        Color::Black => writeln!(buffer, "XXC black1")?,
+        _ => writeln!(buffer, "XXC white1")?,
+    }
+
+    Ok(buffer)
+}
+
+// Before the tournament starts there are no pairing numbers, so the rating order is used
+fn starting_ranks(tournament: &Tournament) -> BTreeMap<PlayerId, usize> {
+    tournament
+        .get_players()
+        .values()
+        .sorted_by_key(|x| (x.pairing_number, Reverse(x.info.clone())))
+        .enumerate()
+        .map(|(i, x)| (x.info.id, i + 1))
+        .collect()
+}
+
+// Scoring where a win is worth two draws is written on the usual 1-½-0 scale, any other
+// scoring in its own units, e.g. 3 points for a win
+fn to_points(score: u8, (win, draw, _): ResultScoreConfig) -> f32 {
+    if win > 0 && win == 2 * draw {
+        f32::from(score) / f32::from(win)
+    } else {
+        f32::from(score)
+    }
+}
+
+// The inverse of `to_points`, from the W, D and L codes of the XXS record
+fn result_scores(points: &BTreeMap<String, f32>) -> Result<ResultScoreConfig, Box<dyn Error>> {
+    let get = |code: &str, default: f32| points.get(code).copied().unwrap_or(default);
+    let (win, draw, lose) = (get("W", 1.0), get("D", 0.5), get("L", 0.0));
+
+    let scale = if win > 0.0 && (win - 2.0 * draw).abs() < f32::EPSILON {
+        2.0 / win
+    } else {
+        1.0
+    };
+
+    let score = |points: f32| {
+        let score = points * scale;
+        if score.fract() == 0.0 && (0.0..=f32::from(u8::MAX)).contains(&score) {
+            Ok(score as u8)
+        } else {
+            Err(format!("Unsupported scoring W={win} D={draw} L={lose}"))
+        }
+    };
+
+    Ok((score(win)?, score(draw)?, score(lose)?))
+}
+
+// The PAB code of the XXS record gives the points of a pairing-allocated bye
+fn bye_value(
+    points: &BTreeMap<String, f32>,
+    result_scores: ResultScoreConfig,
+) -> Result<ByeValue, Box<dyn Error>> {
+    let Some(&bye) = points.get("PAB") else {
+        return Ok(ByeValue::default());
+    };
+
+    [ByeValue::Win, ByeValue::Draw, ByeValue::Loss]
+        .into_iter()
+        .find(|x| {
+            let score = x.result().scores(result_scores).0;
+            (to_points(score, result_scores) - bye).abs() < f32::EPSILON
+        })
+        .ok_or_else(|| format!("Unsupported bye value PAB={bye}").into())
+}
+
+// TRF16 writes titles as lowercase codes, w for the women's titles
+const fn title_code(title: Title) -> &'static str {
+    match title {
+        Title::None => "",
+        Title::WCM => "wc",
+        Title::WFM => "wf",
+        Title::CM => "c",
+        Title::WIM => "wm",
+        Title::FM => "f",
+        Title::WGM => "wg",
+        Title::IM => "m",
+        Title::GM => "g",
+    }
+}
+
+// Files from other programs often use the FIDE abbreviations instead
+fn parse_title(code: &str) -> Option<Title> {
+    match code {
+        "wc" => Some(Title::WCM),
+        "wf" => Some(Title::WFM),
+        "c" => Some(Title::CM),
+        "wm" => Some(Title::WIM),
+        "f" => Some(Title::FM),
+        "wg" => Some(Title::WGM),
+        "m" => Some(Title::IM),
+        "g" => Some(Title::GM),
+        _ => Title::from_abbreviation(code),
+    }
+}
+
+fn round_entry(
+    game: &Game,
+    player_id: PlayerId,
+    ranks: &BTreeMap<PlayerId, usize>,
+) -> (Option<usize>, char, char) {
+    let is_left = game.pairing.left() == player_id;
+    let Some(opponent) = game.opponent_of(player_id) else {
+        // An allocated bye is worth the tournament's bye value, written to the XXS record
+        let result = match game.result {
+            _ if game.is_allocated_bye() => 'U',
+            Some(GameResult::Draw) => 'H',
+            Some(GameResult::ZeroPointBye) => 'Z',
+            _ => 'F',
+        };
+
+        return (None, '-', result);
+    };
+
+    let (color_left, color_right) = game.pairing.colors().unwrap_or((Color::None, Color::None));
+    let color = match if is_left { color_left } else { color_right } {
+        Color::White => 'w',
+        Color::Black => 'b',
+        Color::None => '-',
+    };
+
+    let result = match game.result {
+        // TRF has no code for adjourned games, they are written as still being played
+        None | Some(GameResult::Adjourned) => ' ',
+        Some(GameResult::Draw) => '=',
+        Some(GameResult::ZeroPoints | GameResult::ZeroPointBye) => '0',
+        Some(GameResult::DoubleForfeit) => '-',
+        Some(GameResult::LeftWins) => {
+            if is_left {
+                '1'
+            } else {
+                '0'
+            }
+        }
+        Some(GameResult::RightWins) => {
+            if is_left {
+                '0'
+            } else {
+                '1'
+            }
+        }
+        Some(GameResult::LeftWinsByForfeit) => {
+            if is_left {
+                '+'
+            } else {
+                '-'
+            }
+        }
+        Some(GameResult::RightWinsByForfeit) => {
+            if is_left {
+                '-'
+            } else {
+                '+'
+            }
+        }
+    };
+
+    (ranks.get(&opponent).copied(), color, result)
+}
+
+// 1-based inclusive columns, trimmed. Short lines give empty fields.
+fn field(line: &[char], from: usize, to: usize) -> String {
+    let to = to.min(line.len());
+    if from > to {
+        return String::new();
+    }
+
+    line[from - 1..to]
+        .iter()
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+fn parse_player(line: &[char]) -> Result<PlayerLine, Box<dyn Error>> {
+    let rank = field(line, 5, 8)
+        .parse::<usize>()
+        .map_err(|_| "Invalid starting rank")?;
+
+    let sex = match field(line, 10, 10).as_str() {
+        "m" => Sex::Male,
+        "w" | "f" => Sex::Female,
+        _ => Sex::Unknown,
+    };
+
+    let title = field(line, 11, 13);
+    let title = parse_title(&title).ok_or_else(|| format!("Invalid title \"{title}\""))?;
+
+    let rating = field(line, 49, 52);
+    let fide_id = field(line, 58, 68);
+
+    let info = PlayerInfo {
+        id: rank as PlayerId,
+        name: field(line, 15, 47),
+        rating: if rating.is_empty() {
+            0
+        } else {
+            rating.parse()?
+        },
+        title,
+        fide_id: if fide_id.is_empty() {
+            None
+        } else {
+            Some(fide_id.parse()?)
+        },
+        federation: field(line, 54, 56),
+        birth_date: field(line, 70, 79),
+        sex,
+        ..Default::default()
+    };
+
+    let mut rounds = Vec::new();
+    let mut start = FIRST_ROUND_COLUMN;
+
+    while start <= line.len() {
+        let opponent = field(line, start, start + 3);
+        let color = line.get(start + 4).copied().unwrap_or(' ');
+        let result = line.get(start + 6).copied().unwrap_or(' ');
+
+        let entry = if opponent.is_empty() && color == ' ' && result == ' ' {
+            None
+        } else {
+            Some(RoundEntry {
+                opponent: if opponent.is_empty() {
+                    0
+                } else {
+                    opponent.parse()?
+                },
+                color,
+                result,
+            })
+        };
+
+        rounds.push(entry);
+        start += ROUND_WIDTH;
+    }
+
+    // Trailing blank rounds haven't been paired yet
+    while rounds.last().is_some_and(Option::is_none) {
+        rounds.pop();
+    }
+
+    Ok(PlayerLine { rank, info, rounds })
+}
+
+fn build_round(
+    players: &[PlayerLine],
+    round: usize,
+    number: u8,
+    bye_value: ByeValue,
+) -> Result<Round, Box<dyn Error>> {
+    let entry = |rank: usize| {
+        players
+            .get(rank - 1)
+            .and_then(|x| x.rounds.get(round))
+            .copied()
+            .flatten()
+    };
+
+    let mut pairings = Vec::new();
+    let mut results = Vec::new();
+
+    for player in players {
+        let Some(own) = entry(player.rank) else {
+            continue;
+        };
+
+        if own.opponent == 0 {
+            let (result, bye) = match own.result {
+                'U' => (bye_value.result(), Bye::Allocated),
+                'F' => (GameResult::LeftWins, Bye::Requested),
+                'H' => (GameResult::Draw, Bye::Requested),
+                'Z' => (GameResult::ZeroPointBye, Bye::Requested),
+                // Not paired in this round
+                _ => continue,
+            };
+
+            pairings.push(Pairing::bye(player.rank as PlayerId));
+            results.push((Some(result), Some(bye)));
+            continue;
+        }
+
+        // Every game is listed by both players, it's added by the one with the lower rank
+        if own.opponent < player.rank {
+            continue;
+        }
+
+        let other = entry(own.opponent)
+            .filter(|x| x.opponent == player.rank)
+            .ok_or_else(|| {
+                format!(
+                    "Round {}: players {} and {} disagree",
+                    round + 1,
+                    player.rank,
+                    own.opponent
+                )
+            })?;
+
+        // The player with white is on the left, forfeits may not have colours at all
+        let (left, right) = if own.color == 'b' {
+            (other, own)
+        } else {
+            (own, other)
+        };
+        let (left_rank, right_rank) = if own.color == 'b' {
+            (own.opponent, player.rank)
+        } else {
+            (player.rank, own.opponent)
+        };
+
+        let result = match (left.result, right.result) {
+            ('1' | 'W', _) => Some(GameResult::LeftWins),
+            ('0' | 'L', '0' | 'L') => Some(GameResult::ZeroPoints),
+            ('0' | 'L', _) => Some(GameResult::RightWins),
+            ('=' | 'D', _) => Some(GameResult::Draw),
+            ('+', _) => Some(GameResult::LeftWinsByForfeit),
+            ('-', '-') => Some(GameResult::DoubleForfeit),
+            ('-', _) => Some(GameResult::RightWinsByForfeit),
+            // Paired, but not finished yet
+            _ => None,
+        };
+
+        let (color_left, color_right) = match (left.color, right.color) {
+            ('w', _) | (_, 'b') => (Color::White, Color::Black),
+            _ if result.is_some_and(|x| !x.is_played()) => (Color::None, Color::None),
+            _ => (Color::White, Color::Black),
+        };
+
+        pairings.push(Pairing::new(
+            left_rank as PlayerId,
+            right_rank as PlayerId,
+            color_left,
+            color_right,
+        ));
+        results.push((result, None));
+    }
+
+    let mut round = Round::new(number, pairings, bye_value);
+    for (game, (result, bye)) in round.games.iter_mut().zip(results) {
+        game.result = result;
+        game.bye = bye;
+    }
+
+    Ok(round)
+}
+
+// A tournament from every player's rounds as (opponent, colour, result) in TRF codes, 0 being no
+// opponent. Players are numbered from 1 in the order of `ratings`.
+#[cfg(test)]
+pub(crate) fn replay(
+    ratings: &[u16],
+    rounds: &[&[(usize, char, char)]],
+    number_of_rounds: u8,
+    pairing_system: PairingSystemType,
+) -> Tournament {
+    let mut input = String::new();
+    for (rank, (rating, games)) in (1..).zip(ratings.iter().zip(rounds)) {
+        let start = format!(
+            "001 {rank:>4}      {:<33} {rating:>4}",
+            format!("Player {rank}")
+        );
+        write!(input, "{start:<89}").unwrap();
+
+        for &(opponent, color, result) in *games {
+            if opponent == 0 {
+                write!(input, "  0000 {color} {result}").unwrap();
+            } else {
+                write!(input, "  {opponent:>4} {color} {result}").unwrap();
+            }
+        }
+
+        input.push('\n');
+    }
+
+    writeln!(input, "XXR {number_of_rounds}").unwrap();
+    parse(&input, pairing_system).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, write};
+    use crate::chess_types::{ByeValue, GameResult, Title};
+    use crate::pairing_system::PairingSystemType;
+    use crate::player::{Player, PlayerId};
+    use crate::tournament::Tournament;
+
+    fn player(id: PlayerId) -> Player {
+        Player::new(
+            id,
+            &format!("Player {id}"),
+            Title::None,
+            2400 - 50 * id as u16,
+        )
+    }
+
+    // Byes of every kind, forfeits, a double forfeit and two late entries
+    fn tournament() -> Tournament {
+        let mut tournament = Tournament::new(5, PairingSystemType::ClassicSwiss);
+        for id in 1..=7 {
+            tournament.add_player(player(id));
+        }
+
+        tournament.start().unwrap();
+        tournament.request_bye(2, 1, ByeValue::Draw).unwrap();
+        tournament.request_bye(5, 2, ByeValue::Loss).unwrap();
+        tournament
+            .add_late_entry(player(8), 2, Some(ByeValue::Draw))
+            .unwrap();
+        tournament.add_late_entry(player(9), 3, None).unwrap();
+
+        let results = [
+            GameResult::LeftWins,
+            GameResult::LeftWinsByForfeit,
+            GameResult::Draw,
+            GameResult::RightWinsByForfeit,
+            GameResult::DoubleForfeit,
+            GameResult::RightWins,
+        ];
+
+        for round in 0..3 {
+            let pairings = tournament.pair().unwrap();
+            for (i, pairing) in pairings.iter().filter(|x| !x.is_bye()).enumerate() {
+                let result = results[(i + round) % results.len()];
+                tournament.submit_result(pairing, result).unwrap();
+            }
+
+            tournament.close_round().unwrap();
+        }
+
+        tournament
+    }
+
+    #[test]
+    fn round_trip() {
+        let tournament = tournament();
+        let trf = write(&tournament).unwrap();
+        let parsed = parse(&trf, PairingSystemType::ClassicSwiss).unwrap();
+
+        assert_eq!(write(&parsed).unwrap(), trf);
+        assert_eq!(parsed.current_round(), tournament.current_round());
+
+        // Every kind of bye made it to the file
+        for code in ["0000 - U", "0000 - H", "0000 - Z", "0000 - -", " + ", " - "] {
+            assert!(trf.contains(code), "{code:?} missing from\n{trf}");
+        }
+
+        for (original, parsed) in tournament
+            .get_players()
+            .values()
+            .zip(parsed.get_players().values())
+        {
+            assert_eq!(original.score, parsed.score, "Player {}", original.info.id);
+            assert_eq!(
+                original.color_history, parsed.color_history,
+                "Player {}",
+                original.info.id
+            );
+            assert_eq!(
+                original.previous_opponents, parsed.previous_opponents,
+                "Player {}",
+                original.info.id
+            );
+        }
+    }
+
+    #[test]
+    fn titles_use_the_trf_codes() {
+        let titles = [
+            (Title::GM, "g"),
+            (Title::IM, "m"),
+            (Title::FM, "f"),
+            (Title::CM, "c"),
+            (Title::WGM, "wg"),
+            (Title::WIM, "wm"),
+            (Title::WFM, "wf"),
+            (Title::WCM, "wc"),
+        ];
+
+        let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+        for (id, (title, _)) in (1..).zip(titles) {
+            tournament.add_player(Player::new(id, &format!("Player {id}"), title, 2000));
+        }
+
+        let trf = write(&tournament).unwrap();
+        for (id, (_, code)) in (1..).zip(titles) {
+            let name = format!(" Player {id} ");
+            let line = trf.lines().find(|x| x.contains(&name)).unwrap();
+            assert_eq!(line[10..13].trim(), code, "{line}");
+        }
+
+        // Ids are starting ranks after parsing, players are matched by name
+        let parsed = parse(&trf, PairingSystemType::ClassicSwiss).unwrap();
+        for player in parsed.get_players().values() {
+            let original = tournament
+                .get_players()
+                .values()
+                .find(|x| x.info.name == player.info.name)
+                .unwrap();
+            assert_eq!(original.info.title, player.info.title);
+        }
+
+        // The FIDE abbreviations are still understood
+        let upper = trf.replacen(" wg ", "WGM ", 1);
+        let parsed = parse(&upper, PairingSystemType::ClassicSwiss).unwrap();
+        assert!(
+            parsed
+                .get_players()
+                .values()
+                .any(|x| x.info.title == Title::WGM)
+        );
+    }
+
+    #[test]
+    fn points_follow_the_scoring() {
+        let mut tournament =
+            Tournament::new_with_result_scores(3, PairingSystemType::ClassicSwiss, (3, 1, 0));
+        for id in 1..=4 {
+            tournament.add_player(player(id));
+        }
+
+        tournament.start().unwrap();
+        let pairings = tournament.pair().unwrap();
+        tournament
+            .submit_result(&pairings[0], GameResult::LeftWins)
+            .unwrap();
+        tournament
+            .submit_result(&pairings[1], GameResult::Draw)
+            .unwrap();
+        tournament.close_round().unwrap();
+
+        let trf = write(&tournament).unwrap();
+        assert!(trf.contains("XXS W=3 D=1 L=0"), "{trf}");
+
+        let winner = pairings[0].left() as usize;
+        let line = trf
+            .lines()
+            .find(|x| x.starts_with(&format!("001 {winner:>4}")))
+            .unwrap();
+        assert_eq!(line[80..84].trim(), "3.0", "{line}");
+
+        let parsed = parse(&trf, PairingSystemType::ClassicSwiss).unwrap();
+        assert_eq!(parsed.result_scores(), (3, 1, 0));
+        assert_eq!(parsed.get_player(pairings[0].left()).unwrap().score, 3);
+        assert_eq!(parsed.get_player(pairings[1].left()).unwrap().score, 1);
+
+        // The usual scoring keeps half points
+        let trf = write(&super::tests::tournament()).unwrap();
+        assert!(trf.contains("XXS W=1 D=0.5 L=0"), "{trf}");
+        assert_eq!(
+            parse(&trf, PairingSystemType::ClassicSwiss)
+                .unwrap()
+                .result_scores(),
+            (2, 1, 0)
+        );
+    }
+
+    #[test]
+    fn allocated_byes_keep_their_value() {
+        for bye_value in [ByeValue::Draw, ByeValue::Loss] {
+            let mut tournament = Tournament::new(3, PairingSystemType::ClassicSwiss);
+            tournament.set_bye_value(bye_value);
+            for id in 1..=5 {
+                tournament.add_player(player(id));
+            }
+
+            tournament.start().unwrap();
+            for _ in 0..2 {
+                let pairings = tournament.pair().unwrap();
+                for pairing in pairings.iter().filter(|x| !x.is_bye()) {
+                    tournament
+                        .submit_result(pairing, GameResult::LeftWins)
+                        .unwrap();
+                }
+
+                tournament.close_round().unwrap();
+            }
+
+            let trf = write(&tournament).unwrap();
+            assert!(trf.contains("0000 - U"), "{trf}");
+
+            let parsed = parse(&trf, PairingSystemType::ClassicSwiss).unwrap();
+            assert_eq!(parsed.bye_value(), bye_value);
+            assert_eq!(write(&parsed).unwrap(), trf);
+
+            for (original, parsed) in tournament
+                .get_players()
+                .values()
+                .zip(parsed.get_players().values())
+            {
+                assert_eq!(
+                    original.has_bye, parsed.has_bye,
+                    "Player {}",
+                    original.info.id
+                );
+                assert_eq!(original.score, parsed.score, "Player {}", original.info.id);
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use itertools::Itertools as _;
+
+use crate::player::{PlayerId, PlayerInfo};
+use crate::random::Rng;
+
+// Which players should be kept apart until the last rounds of a round-robin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Affiliation {
+    #[default]
+    Federation,
+    Club,
+}
+
+impl Affiliation {
+    #[inline]
+    #[must_use]
+    pub fn of(self, info: &PlayerInfo) -> &str {
+        match self {
+            Self::Federation => &info.federation,
+            Self::Club => &info.club,
+        }
+    }
+}
+
+// FIDE C.05 Annex 2, groups A to D of Berger table numbers for 9/10, 11/12... 23/24 players
+const VARMA_TABLES: [[&[usize]; 4]; 8] = [
+    [&[3, 4, 8], &[5, 7, 9], &[1, 6], &[2, 10]],
+    [&[4, 5, 9, 10], &[1, 2, 7], &[6, 8, 12], &[3, 11]],
+    [&[4, 5, 6, 11, 12], &[1, 2, 8, 9], &[7, 10, 13], &[3, 14]],
+    [
+        &[5, 6, 7, 12, 13, 14],
+        &[1, 2, 3, 9, 10],
+        &[8, 11, 15],
+        &[4, 16],
+    ],
+    [
+        &[5, 6, 7, 8, 14, 15, 16],
+        &[1, 2, 3, 10, 11, 12],
+        &[9, 13, 17],
+        &[4, 18],
+    ],
+    [
+        &[6, 7, 8, 9, 15, 16, 17, 18],
+        &[1, 2, 3, 11, 12, 13, 14],
+        &[5, 10, 19],
+        &[4, 20],
+    ],
+    [
+        &[6, 7, 8, 9, 10, 17, 18, 19, 20],
+        &[1, 2, 3, 4, 12, 13, 14, 15],
+        &[5, 11, 21],
+        &[16, 22],
+    ],
+    [
+        &[6, 7, 8, 9, 10, 11, 19, 20, 21, 22, 23],
+        &[1, 2, 3, 4, 5, 14, 15, 16, 17],
+        &[12, 18],
+        &[13, 24],
+    ],
+];
+
+// The Varma groups for `player_count` players. Smaller fields use the 9/10 table without the
+// numbers they don't have, and an odd field leaves out the last number, which is the bye.
+#[must_use]
+pub fn varma_groups(player_count: usize) -> Option<[Vec<usize>; 4]> {
+    let table = VARMA_TABLES.get(player_count.saturating_sub(9) >> 1)?;
+    Some(table.map(|group| {
+        group
+            .iter()
+            .copied()
+            .filter(|&x| x <= player_count)
+            .collect()
+    }))
+}
+
+// Draws a Berger table number for every player, so that players of the same federation or club
+// share a group as far as possible. The largest delegations draw first, each from the smallest
+// group that still holds all of them, or from the largest ones left when none does. Players
+// without one draw last.
+pub(crate) fn allocate(
+    players: &[&PlayerInfo],
+    affiliation: Affiliation,
+    rng: &mut Rng,
+) -> Result<BTreeMap<PlayerId, usize>, Box<dyn Error>> {
+    let Some(mut groups) = varma_groups(players.len()) else {
+        return Err(format!("There are no Varma tables for {} players", players.len()).into());
+    };
+
+    for group in &mut groups {
+        rng.shuffle(group);
+    }
+
+    let delegations = players
+        .iter()
+        .into_group_map_by(|x| affiliation.of(x))
+        .into_iter()
+        .sorted_by_key(|(name, members)| (name.is_empty(), Reverse(members.len()), *name))
+        .collect::<Vec<_>>();
+
+    let mut numbers = BTreeMap::new();
+    for (_, mut members) in delegations {
+        while !members.is_empty() {
+            let index = (0..groups.len())
+                .filter(|&i| groups[i].len() >= members.len())
+                .min_by_key(|&i| groups[i].len())
+                .or_else(|| {
+                    (0..groups.len())
+                        .filter(|&i| !groups[i].is_empty())
+                        .max_by_key(|&i| groups[i].len())
+                })
+                .ok_or("Ran out of Varma numbers")?;
+
+            let count = groups[index].len().min(members.len());
+            for (info, number) in members.drain(..count).zip(groups[index].drain(..count)) {
+                numbers.insert(info.id, number);
+            }
+        }
+    }
+
+    Ok(numbers)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{Affiliation, allocate, varma_groups};
+    use crate::chess_types::Title;
+    use crate::player::PlayerInfo;
+    use crate::random::Rng;
+
+    // C.05 Annex 2, groups A, B, C and D for 9/10 to 23/24 players
+    const ANNEX_2: [(usize, [&[usize]; 4]); 8] = [
+        (10, [&[3, 4, 8], &[5, 7, 9], &[1, 6], &[2, 10]]),
+        (12, [&[4, 5, 9, 10], &[1, 2, 7], &[6, 8, 12], &[3, 11]]),
+        (
+            14,
+            [&[4, 5, 6, 11, 12], &[1, 2, 8, 9], &[7, 10, 13], &[3, 14]],
+        ),
+        (
+            16,
+            [
+                &[5, 6, 7, 12, 13, 14],
+                &[1, 2, 3, 9, 10],
+                &[8, 11, 15],
+                &[4, 16],
+            ],
+        ),
+        (
+            18,
+            [
+                &[5, 6, 7, 8, 14, 15, 16],
+                &[1, 2, 3, 10, 11, 12],
+                &[9, 13, 17],
+                &[4, 18],
+            ],
+        ),
+        (
+            20,
+            [
+                &[6, 7, 8, 9, 15, 16, 17, 18],
+                &[1, 2, 3, 11, 12, 13, 14],
+                &[5, 10, 19],
+                &[4, 20],
+            ],
+        ),
+        (
+            22,
+            [
+                &[6, 7, 8, 9, 10, 17, 18, 19, 20],
+                &[1, 2, 3, 4, 12, 13, 14, 15],
+                &[5, 11, 21],
+                &[16, 22],
+            ],
+        ),
+        (
+            24,
+            [
+                &[6, 7, 8, 9, 10, 11, 19, 20, 21, 22, 23],
+                &[1, 2, 3, 4, 5, 14, 15, 16, 17],
+                &[12, 18],
+                &[13, 24],
+            ],
+        ),
+    ];
+
+    // An odd field leaves out the last number, the bye
+    #[test]
+    fn tables_follow_annex_2() {
+        for (player_count, table) in ANNEX_2 {
+            let expected = table.map(<[usize]>::to_vec);
+            assert_eq!(varma_groups(player_count), Some(expected.clone()));
+
+            let odd = expected.map(|x| x.into_iter().filter(|&x| x < player_count).collect());
+            assert_eq!(varma_groups(player_count - 1), Some(odd));
+        }
+
+        assert_eq!(varma_groups(25), None);
+    }
+
+    // Every number of the Berger table is in exactly one group
+    #[test]
+    fn groups_share_out_the_table() {
+        for player_count in 2..=24 {
+            let groups = varma_groups(player_count).unwrap();
+            let numbers = groups.iter().flatten().copied().collect::<BTreeSet<_>>();
+
+            assert_eq!(numbers.len(), groups.iter().map(Vec::len).sum::<usize>());
+            assert_eq!(numbers, (1..=player_count).collect());
+        }
+    }
+
+    #[test]
+    fn delegations_draw_from_one_group() {
+        let players = (1..=10)
+            .map(|id| {
+                let mut info = PlayerInfo::new(id, &format!("Player {id}"), Title::None, 2000);
+                info.federation = if id <= 3 { "GEO" } else { "" }.to_owned();
+                info
+            })
+            .collect::<Vec<_>>();
+        let players = players.iter().collect::<Vec<_>>();
+
+        let numbers = allocate(&players, Affiliation::Federation, &mut Rng::new(3)).unwrap();
+        assert_eq!(
+            numbers.values().copied().collect::<BTreeSet<_>>(),
+            (1..=10).collect()
+        );
+
+        let georgians = (1..=3).map(|id| numbers[&id]).collect::<BTreeSet<_>>();
+        assert!(
+            varma_groups(10)
+                .unwrap()
+                .iter()
+                .any(|x| georgians.iter().all(|number| x.contains(number)))
+        );
+
+        // The same seed draws the same numbers
+        let again = allocate(&players, Affiliation::Federation, &mut Rng::new(3)).unwrap();
+        assert_eq!(numbers, again);
+    }
+}